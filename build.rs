@@ -3,19 +3,92 @@ extern crate cbindgen;
 #[cfg(feature = "ffi")]
 use std::env;
 
-// TODO parse and optimize yaml files into serde binary for faster
-// startup time.
+use std::path::Path;
 
 fn main() {
-    // let mut build_dir = get_cargo_target_dir().unwrap();
-    // build_dir.push("regexes");
+    println!("cargo:rerun-if-changed=build.rs");
+    println!("cargo:rerun-if-changed=regexes");
 
-    // let _ = std::fs::create_dir(build_dir);
+    let out_dir = std::path::PathBuf::from(std::env::var("OUT_DIR").unwrap());
+    compile_regexes(Path::new("regexes"), &out_dir.join("regexes"));
 
-    // let contents = include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/regexes/bots.yml"));
-    // let value: serde_yaml::Value = serde_yaml::from_str(contents).unwrap();
     #[cfg(feature = "ffi")]
-    build_cpp_header();
+    {
+        println!("cargo:rerun-if-changed=src");
+        build_cpp_header();
+    }
+}
+
+/// Parsing 56k+ lines of yaml takes a noticeable amount of time at startup,
+/// so each yaml file is converted into messagepack, which is embedded instead.
+///
+/// Messagepack rather than something more compact like bincode, because it is
+/// self describing, and some of the parsers deserialize into a
+/// `serde_yaml::Value` before deciding what an entry is.
+fn compile_regexes(from: &Path, to: &Path) {
+    std::fs::create_dir_all(to).unwrap();
+
+    for entry in std::fs::read_dir(from).unwrap() {
+        let entry = entry.unwrap();
+        let path = entry.path();
+
+        if entry.file_type().unwrap().is_dir() {
+            compile_regexes(&path, &to.join(entry.file_name()));
+        } else if path.extension().is_some_and(|x| x == "yml") {
+            let contents = std::fs::read_to_string(&path).unwrap();
+            let value: serde_yaml::Value = serde_yaml::from_str(&contents)
+                .unwrap_or_else(|err| panic!("parsing {}: {}", path.display(), err));
+
+            let bytes = rmp_serde::to_vec(&stringify_keys(value)).unwrap();
+
+            let mut file_name = entry.file_name();
+            file_name.push(".msgpack");
+            std::fs::write(to.join(&file_name), bytes).unwrap();
+
+            let mut file_name = entry.file_name();
+            file_name.push(".hash");
+            let hash = fnv1a(contents.as_bytes());
+            std::fs::write(to.join(file_name), hash.to_le_bytes()).unwrap();
+        }
+    }
+}
+
+/// The hash of the yaml a file was compiled from, which goes into the version
+/// of the regex database. Must match `fnv1a` in `src/parsers/database.rs`.
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf29ce484222325, |hash, byte| {
+        (hash ^ *byte as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
+/// serde_yaml will happily deserialize an unquoted `28:` into a `String` key,
+/// but once it has been through a `Value` it is a number, which messagepack
+/// then refuses to deserialize as a string. Every key in the regexes is used
+/// as a string, so just make them strings.
+fn stringify_keys(value: serde_yaml::Value) -> serde_yaml::Value {
+    use serde_yaml::Value;
+
+    match value {
+        Value::Mapping(mapping) => Value::Mapping(
+            mapping
+                .into_iter()
+                .map(|(key, value)| {
+                    let key = match key {
+                        Value::Number(n) if n.is_i64() || n.is_u64() => {
+                            Value::String(n.to_string())
+                        }
+                        Value::Number(n) => panic!("float keys do not survive as strings: {}", n),
+                        key => key,
+                    };
+                    (key, stringify_keys(value))
+                })
+                .collect(),
+        ),
+        Value::Sequence(sequence) => {
+            Value::Sequence(sequence.into_iter().map(stringify_keys).collect())
+        }
+        value => value,
+    }
 }
 
 #[cfg(feature = "ffi")]
@@ -37,22 +110,3 @@ fn build_cpp_header() {
         }
     }
 }
-
-// we can use this to maybe compress the yaml files into a more compact representation
-// before placing into the binary.
-#[allow(dead_code)]
-fn get_cargo_target_dir() -> Result<std::path::PathBuf, Box<dyn std::error::Error>> {
-    let out_dir = std::path::PathBuf::from(std::env::var("OUT_DIR")?);
-    let profile = std::env::var("PROFILE")?;
-    let mut target_dir = None;
-    let mut sub_path = out_dir.as_path();
-    while let Some(parent) = sub_path.parent() {
-        if parent.ends_with(&profile) {
-            target_dir = Some(parent);
-            break;
-        }
-        sub_path = parent;
-    }
-    let target_dir = target_dir.ok_or("not found")?;
-    Ok(target_dir.to_path_buf())
-}
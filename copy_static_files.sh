@@ -2,7 +2,7 @@
 # Usage: ./copy_static_files.sh /path/to/original/device/detector
 #
 # Note, there will be many source files that have to be modified including
-# lists of known browsers and operating systems to keep this library up to date.
+# lists of known browsers, brands and operating systems to keep this library up to date.
 
 ORIGIN_DIR="$1"
 
@@ -16,3 +16,19 @@ cp $ORIGIN_DIR/Tests/Parser/fixtures/*.yml tests/data/fixtures/parser/
 cp $ORIGIN_DIR/Tests/Parser/Client/fixtures/*.yml tests/data/fixtures/parser/client/
 cp $ORIGIN_DIR/Tests/Parser/Device/fixtures/*.yml tests/data/fixtures/parser/device/
 
+
+# The brand short codes live in php rather than yaml, so they are turned into
+# rust here. 'XX' is upstream's code for devices of no known brand.
+{
+    echo "// Generated by copy_static_files.sh from the \`\$deviceBrands\` table in"
+    echo "// upstream's Parser/Device/AbstractDeviceParser.php, don't edit by hand."
+    echo
+    echo "pub(super) fn brand_codes() -> Vec<(&'static str, &'static str)> {"
+    echo "    vec!["
+    sed -n '/static \$deviceBrands = \[/,/\];/p' $ORIGIN_DIR/Parser/Device/AbstractDeviceParser.php \
+        | grep -v "'XX'" \
+        | sed -nE "s/^ *'([^']+)' *=> *'(([^'\\\\]|\\\\.)*)',.*$/        (\"\1\", \"\2\"),/p" \
+        | sed -E "s/\\\\'/'/g"
+    echo "    ]"
+    echo "}"
+} > src/known_brands/codes.rs
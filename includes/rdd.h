@@ -39,12 +39,18 @@ extern "C" {
 
 RDDDeviceDetector *rdd_device_detector_new(uint64_t cache_size);
 
+/// Have bots report the client, device and os they claim to be as well, so
+/// `rdd_client`, `rdd_device` and `rdd_os` can be used alongside `rdd_bot`.
+void rdd_device_detector_detect_bot_devices(RDDDeviceDetector *rdd, bool enabled);
+
 RDDDetection *rdd_lookup(const RDDDeviceDetector *rdd, const char *ua);
 
 const RDDClient *rdd_client(const RDDDetection *rdd);
 
 char *rdd_client_name(const RDDClient *client);
 
+char *rdd_client_short_name(const RDDClient *client);
+
 char *rdd_client_type(const RDDClient *client);
 
 char *rdd_client_version(const RDDClient *client);
@@ -57,6 +63,8 @@ const RDDDevice *rdd_device(const RDDDetection *rdd);
 
 char *rdd_device_brand(const RDDDevice *device);
 
+char *rdd_device_short_name(const RDDDevice *device);
+
 char *rdd_device_model(const RDDDevice *device);
 
 char *rdd_device_type(const RDDDevice *device);
@@ -65,6 +73,8 @@ const RDDOS *rdd_os(const RDDDetection *rdd);
 
 char *rdd_os_name(const RDDOS *os);
 
+char *rdd_os_short_name(const RDDOS *os);
+
 char *rdd_os_version(const RDDOS *os);
 
 char *rdd_os_platform(const RDDOS *os);
@@ -0,0 +1,296 @@
+use std::collections::HashMap;
+use std::hash::{BuildHasher, RandomState};
+use std::sync::{Arc, RwLock};
+
+#[cfg(feature = "cache")]
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use crate::client_hints::ClientHint;
+use crate::device_detector::Detection;
+
+mod disk;
+
+pub use disk::DiskCache;
+
+/// What a detection is cached under. The same user agent can detect
+/// differently depending on its client hints, or on whether and how bots were
+/// looked for, so those are part of it too.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct CacheKey {
+    pub user_agent: String,
+    pub client_hints: Option<ClientHint>,
+    /// See [`DetectionOptions::skip_bot_detection`].
+    ///
+    /// [`DetectionOptions::skip_bot_detection`]: crate::options::DetectionOptions::skip_bot_detection
+    pub skip_bot_detection: bool,
+    /// See [`DetectionOptions::detect_bot_devices`].
+    ///
+    /// [`DetectionOptions::detect_bot_devices`]: crate::options::DetectionOptions::detect_bot_devices
+    pub detect_bot_devices: bool,
+}
+
+impl CacheKey {
+    /// Roughly how many bytes this takes up.
+    pub fn size(&self) -> usize {
+        let hints = self.client_hints.as_ref().map_or(0, |hints| {
+            let strings = [
+                &hints.architecture,
+                &hints.bitness,
+                &hints.model,
+                &hints.ua_full_version,
+                &hints.platform,
+                &hints.platform_version,
+                &hints.app,
+            ];
+            strings
+                .iter()
+                .flat_map(|x| x.as_ref())
+                .map(String::len)
+                .sum::<usize>()
+                + hints
+                    .full_version_list
+                    .iter()
+                    .map(|(brand, version)| brand.len() + version.len())
+                    .sum::<usize>()
+        });
+
+        std::mem::size_of::<Self>() + self.user_agent.len() + hints
+    }
+}
+
+/// Somewhere to keep detections, so the same user agent needn't be detected
+/// over and over. See [`DeviceDetectorBuilder::detection_cache`], which any
+/// implementation of this can be given to.
+///
+/// [`DeviceDetectorBuilder::detection_cache`]: crate::device_detector::DeviceDetectorBuilder::detection_cache
+pub trait DetectionCache: std::fmt::Debug + Send + Sync {
+    fn get(&self, key: &CacheKey) -> Option<Detection>;
+
+    fn insert(&self, key: CacheKey, detection: Detection);
+
+    /// Drops every cached detection.
+    fn clear(&self);
+
+    /// Roughly how many detections are cached right now.
+    fn entries(&self) -> u64;
+
+    /// Another cache set up the same way, but with nothing in it, for a
+    /// detector with different regexes, which can't use any of these
+    /// detections.
+    fn empty(&self) -> Arc<dyn DetectionCache>;
+}
+
+/// Caches nothing, for when a cache has to be given but shouldn't do
+/// anything.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct NoCache;
+
+impl DetectionCache for NoCache {
+    fn get(&self, _key: &CacheKey) -> Option<Detection> {
+        None
+    }
+
+    fn insert(&self, _key: CacheKey, _detection: Detection) {}
+
+    fn clear(&self) {}
+
+    fn entries(&self) -> u64 {
+        0
+    }
+
+    fn empty(&self) -> Arc<dyn DetectionCache> {
+        Arc::new(NoCache)
+    }
+}
+
+/// A plain `HashMap` split into shards, each with its own lock, so threads
+/// rarely wait on each other. Once a shard is full, an arbitrary entry is
+/// dropped to make room for each new one, which is cheaper than keeping
+/// track of which were used least recently, but not as clever about it.
+pub struct ShardedCache {
+    shards: Box<[RwLock<HashMap<CacheKey, Detection>>]>,
+    hasher: RandomState,
+    per_shard: usize,
+}
+
+impl std::fmt::Debug for ShardedCache {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ShardedCache")
+            .field("shards", &self.shards.len())
+            .field("entries", &self.entries())
+            .finish()
+    }
+}
+
+impl ShardedCache {
+    /// Holds about `entries` detections at most, spread across `shards`.
+    pub fn new(entries: usize, shards: usize) -> Self {
+        let shards = shards.max(1);
+        Self {
+            shards: (0..shards).map(|_| Default::default()).collect(),
+            hasher: RandomState::new(),
+            per_shard: entries.div_ceil(shards).max(1),
+        }
+    }
+
+    fn shard(&self, key: &CacheKey) -> &RwLock<HashMap<CacheKey, Detection>> {
+        let hash = self.hasher.hash_one(key) as usize;
+        &self.shards[hash % self.shards.len()]
+    }
+}
+
+impl DetectionCache for ShardedCache {
+    fn get(&self, key: &CacheKey) -> Option<Detection> {
+        self.shard(key).read().unwrap().get(key).cloned()
+    }
+
+    fn insert(&self, key: CacheKey, detection: Detection) {
+        let mut shard = self.shard(&key).write().unwrap();
+
+        if shard.len() >= self.per_shard && !shard.contains_key(&key) {
+            // the iteration order of a HashMap is as good as random
+            if let Some(evict) = shard.keys().next().cloned() {
+                shard.remove(&evict);
+            }
+        }
+
+        shard.insert(key, detection);
+    }
+
+    fn clear(&self) {
+        for shard in self.shards.iter() {
+            shard.write().unwrap().clear();
+        }
+    }
+
+    fn entries(&self) -> u64 {
+        self.shards
+            .iter()
+            .map(|shard| shard.read().unwrap().len() as u64)
+            .sum()
+    }
+
+    fn empty(&self) -> Arc<dyn DetectionCache> {
+        let shards = self.shards.len();
+        Arc::new(ShardedCache::new(self.per_shard * shards, shards))
+    }
+}
+
+/// An in memory cache built on moka, which decides what to keep with TinyLFU
+/// and evicts the least recently used. Entries can also be made to expire,
+/// and the cache can be limited by how much memory it takes up rather than
+/// how many detections it holds.
+#[cfg(feature = "cache")]
+pub struct MokaCache {
+    cache: moka::sync::Cache<CacheKey, Detection>,
+    config: MokaCacheBuilder,
+}
+
+#[cfg(feature = "cache")]
+impl std::fmt::Debug for MokaCache {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("MokaCache")
+            .field("entries", &self.cache.entry_count())
+            .finish()
+    }
+}
+
+#[cfg(feature = "cache")]
+impl MokaCache {
+    /// Holds up to `entries` detections.
+    pub fn new(entries: u64) -> Self {
+        Self::builder().max_entries(entries).build()
+    }
+
+    pub fn builder() -> MokaCacheBuilder {
+        MokaCacheBuilder::default()
+    }
+}
+
+/// Builds a [`MokaCache`]. Without a limit set, it holds nothing.
+#[cfg(feature = "cache")]
+#[derive(Clone, Debug, Default)]
+pub struct MokaCacheBuilder {
+    capacity: u64,
+    weigh_by_bytes: bool,
+    time_to_live: Option<Duration>,
+    time_to_idle: Option<Duration>,
+}
+
+#[cfg(feature = "cache")]
+impl MokaCacheBuilder {
+    /// Holds up to `entries` detections.
+    pub fn max_entries(mut self, entries: u64) -> Self {
+        self.capacity = entries;
+        self.weigh_by_bytes = false;
+        self
+    }
+
+    /// Holds up to roughly `bytes` of user agents, client hints and
+    /// detections, however many detections that turns out to be.
+    pub fn max_bytes(mut self, bytes: u64) -> Self {
+        self.capacity = bytes;
+        self.weigh_by_bytes = true;
+        self
+    }
+
+    /// Drops detections this long after they were cached.
+    pub fn time_to_live(mut self, ttl: Duration) -> Self {
+        self.time_to_live = Some(ttl);
+        self
+    }
+
+    /// Drops detections that haven't been used for this long.
+    pub fn time_to_idle(mut self, tti: Duration) -> Self {
+        self.time_to_idle = Some(tti);
+        self
+    }
+
+    pub fn build(self) -> MokaCache {
+        let config = self.clone();
+        let mut builder = moka::sync::Cache::builder().max_capacity(self.capacity);
+
+        if self.weigh_by_bytes {
+            builder = builder.weigher(|key: &CacheKey, detection: &Detection| {
+                let size = key.size() + detection.size();
+                size.try_into().unwrap_or(u32::MAX)
+            });
+        }
+        if let Some(ttl) = self.time_to_live {
+            builder = builder.time_to_live(ttl);
+        }
+        if let Some(tti) = self.time_to_idle {
+            builder = builder.time_to_idle(tti);
+        }
+
+        MokaCache {
+            cache: builder.build(),
+            config,
+        }
+    }
+}
+
+#[cfg(feature = "cache")]
+impl DetectionCache for MokaCache {
+    fn get(&self, key: &CacheKey) -> Option<Detection> {
+        self.cache.get(key)
+    }
+
+    fn insert(&self, key: CacheKey, detection: Detection) {
+        self.cache.insert(key, detection)
+    }
+
+    fn clear(&self) {
+        self.cache.invalidate_all()
+    }
+
+    fn entries(&self) -> u64 {
+        self.cache.entry_count()
+    }
+
+    fn empty(&self) -> Arc<dyn DetectionCache> {
+        Arc::new(self.config.clone().build())
+    }
+}
@@ -0,0 +1,424 @@
+use anyhow::{Context, Result};
+
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+
+use std::collections::HashMap;
+use std::fs::{File, OpenOptions};
+use std::io::{BufReader, ErrorKind, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, Weak};
+
+use super::{CacheKey, DetectionCache};
+use crate::device_detector::{Detection, KnownDevice};
+use crate::known_browsers::AvailableBrowser;
+use crate::parsers::bot::Bot;
+use crate::parsers::client::{Client, ClientType};
+use crate::parsers::device::{Device, DeviceType};
+use crate::parsers::oss::OS;
+
+// The number at the end goes up whenever the stored types below change, so
+// files written before then are started over rather than misread.
+const HEADER: &str = concat!(
+    "rust-device-detector ",
+    env!("CARGO_PKG_VERSION"),
+    " detections 5\n"
+);
+
+// Caches that are open in this process, by file. The file is locked while
+// open, so a second detector on the same directory has to share it.
+static OPEN: Lazy<Mutex<HashMap<PathBuf, Weak<DiskCache>>>> = Lazy::new(Default::default);
+
+/// Keeps detections in a file, so they outlast the process. Each set of
+/// regexes gets a file of its own, named after
+/// [`DeviceDetector::database_version`], so detections made with other
+/// regexes are never used, and their files are deleted when a cache is
+/// opened on the same directory.
+///
+/// The file is an append only log, which is read into an index of where
+/// each detection is when opened. Nothing is ever evicted, short of
+/// [`DetectionCache::clear`]. Only one process can have it open at a time.
+///
+/// [`DeviceDetector::database_version`]: crate::device_detector::DeviceDetector::database_version
+pub struct DiskCache {
+    path: PathBuf,
+    log: Mutex<Log>,
+}
+
+struct Log {
+    file: File,
+    index: HashMap<CacheKey, (u64, u32)>,
+    end: u64,
+}
+
+impl std::fmt::Debug for DiskCache {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("DiskCache")
+            .field("path", &self.path)
+            .field("entries", &self.entries())
+            .finish()
+    }
+}
+
+impl DiskCache {
+    /// Opens, or creates, the cache in `dir` for detections made with the
+    /// regexes whose version is `database_version`.
+    pub fn open(dir: impl AsRef<Path>, database_version: u64) -> Result<Arc<DiskCache>> {
+        let dir = dir.as_ref();
+        std::fs::create_dir_all(dir).with_context(|| format!("creating {}", dir.display()))?;
+        let dir = dir.canonicalize()?;
+
+        let name = format!("detections-{:016x}.bin", database_version);
+        let path = dir.join(&name);
+
+        let mut open = OPEN.lock().unwrap();
+        open.retain(|_, cache| cache.strong_count() > 0);
+
+        if let Some(cache) = open.get(&path).and_then(Weak::upgrade) {
+            return Ok(cache);
+        }
+
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(&path)
+            .with_context(|| format!("opening {}", path.display()))?;
+        file.try_lock()
+            .with_context(|| format!("locking {}", path.display()))?;
+
+        let log = Log::read(file).with_context(|| format!("reading {}", path.display()))?;
+        remove_stale(&dir, &name);
+
+        let cache = Arc::new(DiskCache {
+            path: path.clone(),
+            log: Mutex::new(log),
+        });
+        open.insert(path, Arc::downgrade(&cache));
+
+        Ok(cache)
+    }
+
+    /// The file detections are kept in.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+// Deletes the files of other versions in `dir`, unless they are in use.
+fn remove_stale(dir: &Path, current: &str) {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+
+    for entry in entries.flatten() {
+        let name = entry.file_name();
+        let Some(name) = name.to_str() else {
+            continue;
+        };
+        if name == current || !name.starts_with("detections-") || !name.ends_with(".bin") {
+            continue;
+        }
+
+        let path = entry.path();
+        let unused = File::open(&path).is_ok_and(|file| file.try_lock().is_ok());
+        if unused {
+            let _ = std::fs::remove_file(&path);
+        }
+    }
+}
+
+impl Log {
+    // Each record is the length of its key and of its detection, as little
+    // endian u32s, followed by both in messagepack.
+    fn read(mut file: File) -> Result<Log> {
+        let len = file.metadata()?.len();
+
+        let mut header = vec![0; HEADER.len()];
+        let current = len >= HEADER.len() as u64
+            && file.read_exact(&mut header).is_ok()
+            && header == HEADER.as_bytes();
+
+        if !current {
+            // new, or written by another version of this crate
+            file.set_len(0)?;
+            file.seek(SeekFrom::Start(0))?;
+            file.write_all(HEADER.as_bytes())?;
+
+            return Ok(Log {
+                file,
+                index: HashMap::new(),
+                end: HEADER.len() as u64,
+            });
+        }
+
+        let mut index = HashMap::new();
+        let mut end = HEADER.len() as u64;
+
+        {
+            let mut reader = BufReader::new(&mut file);
+            while let Ok(Some((key, key_len, detection_len))) = Self::read_key(&mut reader) {
+                let offset = end + 8 + key_len as u64;
+                if offset + detection_len as u64 > len {
+                    break;
+                }
+                reader.seek_relative(detection_len as i64)?;
+                index.insert(key, (offset, detection_len));
+                end = offset + detection_len as u64;
+            }
+        }
+
+        // whatever follows the last whole record was cut off mid write
+        if end < len {
+            file.set_len(end)?;
+        }
+
+        Ok(Log { file, index, end })
+    }
+
+    fn read_key(reader: &mut impl Read) -> Result<Option<(CacheKey, u32, u32)>> {
+        let mut lengths = [0; 8];
+        match reader.read_exact(&mut lengths) {
+            Ok(()) => {}
+            Err(err) if err.kind() == ErrorKind::UnexpectedEof => return Ok(None),
+            Err(err) => return Err(err.into()),
+        }
+
+        let key_len = u32::from_le_bytes(lengths[..4].try_into().unwrap());
+        let detection_len = u32::from_le_bytes(lengths[4..].try_into().unwrap());
+
+        let mut key = vec![0; key_len as usize];
+        reader.read_exact(&mut key)?;
+
+        Ok(Some((rmp_serde::from_slice(&key)?, key_len, detection_len)))
+    }
+
+    fn get(&mut self, key: &CacheKey) -> Result<Option<Detection>> {
+        let Some(&(offset, len)) = self.index.get(key) else {
+            return Ok(None);
+        };
+
+        let mut bytes = vec![0; len as usize];
+        self.file.seek(SeekFrom::Start(offset))?;
+        self.file.read_exact(&mut bytes)?;
+
+        let stored: StoredDetection = rmp_serde::from_slice(&bytes)?;
+        Ok(Some(stored.into()))
+    }
+
+    fn insert(&mut self, key: CacheKey, detection: &Detection) -> Result<()> {
+        if self.index.contains_key(&key) {
+            return Ok(());
+        }
+
+        let key_bytes = rmp_serde::to_vec(&key)?;
+        let detection_bytes = rmp_serde::to_vec(&StoredDetection::from(detection))?;
+
+        let mut record = Vec::with_capacity(8 + key_bytes.len() + detection_bytes.len());
+        record.extend_from_slice(&(key_bytes.len() as u32).to_le_bytes());
+        record.extend_from_slice(&(detection_bytes.len() as u32).to_le_bytes());
+        record.extend_from_slice(&key_bytes);
+        record.extend_from_slice(&detection_bytes);
+
+        self.file.seek(SeekFrom::Start(self.end))?;
+        if let Err(err) = self.file.write_all(&record) {
+            // don't leave half a record behind for the next one to follow
+            let _ = self.file.set_len(self.end);
+            return Err(err.into());
+        }
+
+        let offset = self.end + 8 + key_bytes.len() as u64;
+        self.index
+            .insert(key, (offset, detection_bytes.len() as u32));
+        self.end += record.len() as u64;
+
+        Ok(())
+    }
+
+    fn clear(&mut self) -> Result<()> {
+        self.index.clear();
+        self.end = HEADER.len() as u64;
+        self.file.set_len(self.end)?;
+        Ok(())
+    }
+}
+
+impl DetectionCache for DiskCache {
+    fn get(&self, key: &CacheKey) -> Option<Detection> {
+        match self.log.lock().unwrap().get(key) {
+            Ok(detection) => detection,
+            Err(err) => {
+                eprintln!("error reading {}: {:#}", self.path.display(), err);
+                None
+            }
+        }
+    }
+
+    fn insert(&self, key: CacheKey, detection: Detection) {
+        if let Err(err) = self.log.lock().unwrap().insert(key, &detection) {
+            eprintln!("error writing {}: {:#}", self.path.display(), err);
+        }
+    }
+
+    fn clear(&self) {
+        if let Err(err) = self.log.lock().unwrap().clear() {
+            eprintln!("error clearing {}: {:#}", self.path.display(), err);
+        }
+    }
+
+    fn entries(&self) -> u64 {
+        self.log.lock().unwrap().index.len() as u64
+    }
+
+    /// A file is only ever for the one set of regexes, and without knowing
+    /// which the new ones are, there is nothing to open, so this caches
+    /// nothing. [`DeviceDetectorBuilder::disk_cache`] opens the right file
+    /// for every detector it builds instead.
+    ///
+    /// [`DeviceDetectorBuilder::disk_cache`]: crate::device_detector::DeviceDetectorBuilder::disk_cache
+    fn empty(&self) -> Arc<dyn DetectionCache> {
+        Arc::new(super::NoCache)
+    }
+}
+
+// The detection types leave out of their json some fields that are only
+// used internally, but which a detection read back needs all the same, so
+// they are written out through these instead.
+
+#[allow(clippy::large_enum_variant)]
+#[derive(Serialize, Deserialize)]
+enum StoredDetection {
+    Known {
+        client: Option<StoredClient>,
+        device: Option<StoredDevice>,
+        os: Option<StoredOS>,
+        bot: Option<Bot>,
+    },
+    Bot(Bot),
+    AnonymousBot,
+}
+
+#[derive(Serialize, Deserialize)]
+struct StoredClient {
+    name: String,
+    short_name: Option<String>,
+    version: Option<String>,
+    r#type: ClientType,
+    engine: Option<String>,
+    engine_version: Option<String>,
+    browser: Option<(String, String, Option<String>, bool)>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct StoredDevice {
+    device_type: Option<DeviceType>,
+    brand: Option<String>,
+    short_name: Option<String>,
+    model: Option<String>,
+    mobile_client_hint: bool,
+    touch_enabled: bool,
+}
+
+#[derive(Serialize, Deserialize)]
+struct StoredOS {
+    name: String,
+    short_name: Option<String>,
+    version: Option<String>,
+    platform: Option<String>,
+    family: Option<String>,
+    desktop: bool,
+}
+
+impl From<&Detection> for StoredDetection {
+    fn from(detection: &Detection) -> Self {
+        match detection {
+            Detection::Bot(bot) => StoredDetection::Bot(bot.clone()),
+            Detection::AnonymousBot => StoredDetection::AnonymousBot,
+            Detection::Known(known) => StoredDetection::Known {
+                client: known.client.as_ref().map(|client| StoredClient {
+                    name: client.name.clone(),
+                    short_name: client.short_name.clone(),
+                    version: client.version.clone(),
+                    r#type: client.r#type.clone(),
+                    engine: client.engine.clone(),
+                    engine_version: client.engine_version.clone(),
+                    browser: client.browser.as_ref().map(|browser| {
+                        (
+                            browser.short_name.clone(),
+                            browser.name.clone(),
+                            browser.family.clone(),
+                            browser.mobile_only,
+                        )
+                    }),
+                }),
+                device: known.device.as_ref().map(|device| StoredDevice {
+                    device_type: device.device_type.clone(),
+                    brand: device.brand.clone(),
+                    short_name: device.short_name.clone(),
+                    model: device.model.clone(),
+                    mobile_client_hint: device.mobile_client_hint,
+                    touch_enabled: device.touch_enabled,
+                }),
+                os: known.os.as_ref().map(|os| StoredOS {
+                    name: os.name.clone(),
+                    short_name: os.short_name.clone(),
+                    version: os.version.clone(),
+                    platform: os.platform.clone(),
+                    family: os.family.clone(),
+                    desktop: os.desktop,
+                }),
+                bot: known.bot.clone(),
+            },
+        }
+    }
+}
+
+impl From<StoredDetection> for Detection {
+    fn from(stored: StoredDetection) -> Self {
+        match stored {
+            StoredDetection::Bot(bot) => Detection::Bot(bot),
+            StoredDetection::AnonymousBot => Detection::AnonymousBot,
+            StoredDetection::Known {
+                client,
+                device,
+                os,
+                bot,
+            } => Detection::Known(KnownDevice {
+                client: client.map(|client| Client {
+                    name: client.name,
+                    short_name: client.short_name,
+                    version: client.version,
+                    r#type: client.r#type,
+                    engine: client.engine,
+                    engine_version: client.engine_version,
+                    browser: client
+                        .browser
+                        .map(|(short_name, name, family, mobile_only)| AvailableBrowser {
+                            short_name,
+                            name,
+                            family,
+                            mobile_only,
+                        }),
+                }),
+                device: device.map(|device| Device {
+                    device_type: device.device_type,
+                    brand: device.brand,
+                    short_name: device.short_name,
+                    model: device.model,
+                    mobile_client_hint: device.mobile_client_hint,
+                    touch_enabled: device.touch_enabled,
+                }),
+                os: os.map(|os| OS {
+                    name: os.name,
+                    short_name: os.short_name,
+                    version: os.version,
+                    platform: os.platform,
+                    family: os.family,
+                    desktop: os.desktop,
+                }),
+                bot,
+            }),
+        }
+    }
+}
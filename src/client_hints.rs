@@ -1,9 +1,62 @@
 use anyhow::Result;
 
 use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
 
+use crate::error::Error;
 use crate::parsers::utils::SafeRegex as Regex;
 
+fn invalid_header(name: &str, value: &str, err: anyhow::Error) -> Error {
+    Error::InvalidHeader {
+        name: name.to_owned(),
+        value: value.to_owned(),
+        message: format!("{:#}", err),
+    }
+}
+
+/// Splits a block of http headers, one `Name: value` per line, into the
+/// headers [`ClientHint::from_headers`] takes. Values may contain colons, only
+/// the first one on each line separates the name. A request line such as
+/// `GET / HTTP/1.1` at the top is skipped, so a whole request can be pasted
+/// in, and lines that start with whitespace continue the previous value.
+pub fn parse_header_block(block: &str) -> Result<Vec<(String, String)>, Error> {
+    let mut headers: Vec<(String, String)> = Vec::new();
+
+    for (i, line) in block.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        if i == 0
+            && line
+                .rsplit_once(' ')
+                .is_some_and(|(_, version)| version.trim_end().starts_with("HTTP/"))
+        {
+            continue;
+        }
+
+        if line.starts_with([' ', '\t']) {
+            if let Some((_, value)) = headers.last_mut() {
+                value.push(' ');
+                value.push_str(line.trim());
+                continue;
+            }
+        }
+
+        let Some((name, value)) = line.split_once(':') else {
+            return Err(invalid_header(
+                line.trim(),
+                "",
+                anyhow::anyhow!("expected a header like `Name: value`"),
+            ));
+        };
+
+        headers.push((name.trim().to_owned(), value.trim().to_owned()));
+    }
+
+    Ok(headers)
+}
+
 pub struct ClientHintMapping {
     mappings: Vec<(String, Vec<String>)>,
 }
@@ -36,7 +89,11 @@ impl ClientHintMapping {
 }
 
 // TODO options?
-#[derive(Debug)]
+/// Everything in the headers that can make a difference to a detection. Two
+/// sets of headers that come out the same here detect the same, whatever
+/// order or case they were in and whatever else came with them, which is what
+/// makes this a cache key.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct ClientHint {
     pub architecture: Option<String>,
     pub bitness: Option<String>,
@@ -50,7 +107,7 @@ pub struct ClientHint {
 }
 
 impl ClientHint {
-    pub fn from_headers(headers: Vec<(String, String)>) -> Result<ClientHint> {
+    pub fn from_headers(headers: Vec<(String, String)>) -> Result<ClientHint, Error> {
         let mut architecture = None;
         let mut bitness = None;
         let mut mobile = false;
@@ -77,13 +134,13 @@ impl ClientHint {
                     bitness = Some(value.trim_matches('"').to_owned());
                 }
 
-                "sec-ch-ua-mobile" => {
-                    // the php version interspersed actual headers and mock variable
-                    // names in its code, so I don't know which of these values actually
-                    // could come up in real user agents.
-                    if value == "1" || value == "true" || value == "yes" || value == "?1" {
-                        mobile = true;
-                    }
+                // the php version interspersed actual headers and mock variable
+                // names in its code, so I don't know which of these values actually
+                // could come up in real user agents.
+                "sec-ch-ua-mobile"
+                    if value == "1" || value == "true" || value == "yes" || value == "?1" =>
+                {
+                    mobile = true;
                 }
 
                 "sec-ch-ua-model" => {
@@ -105,25 +162,20 @@ impl ClientHint {
                     }
                 }
 
-                "x-requested-with" => {
-                    if value != "xmlhttprequest" {
-                        app = Some(value.to_owned());
-                    }
+                "x-requested-with" if value != "xmlhttprequest" => {
+                    app = Some(value.to_owned());
                 }
 
                 "sec-ch-ua-full-version" => {
                     ua_full_version = Some(value.trim_matches('"').to_owned());
                 }
 
-                "sec-ch-ua" => {
-                    if full_version_list.is_empty() {
-                        for x in BRAND_REGEX.captures_iter(&value) {
-                            let res = x?;
-                            let brand = res.get(1).map(|x| x.as_str()).unwrap_or_else(|| "");
-                            let brand_version =
-                                res.get(2).map(|x| x.as_str()).unwrap_or_else(|| "");
-                            full_version_list.push((brand.to_owned(), brand_version.to_owned()));
-                        }
+                "sec-ch-ua" if full_version_list.is_empty() => {
+                    for x in BRAND_REGEX.captures_iter(&value) {
+                        let res = x.map_err(|err| invalid_header(&header, &value, err))?;
+                        let brand = res.get(1).map(|x| x.as_str()).unwrap_or_else(|| "");
+                        let brand_version = res.get(2).map(|x| x.as_str()).unwrap_or_else(|| "");
+                        full_version_list.push((brand.to_owned(), brand_version.to_owned()));
                     }
                 }
 
@@ -131,7 +183,7 @@ impl ClientHint {
                     full_version_list.clear();
 
                     for x in BRAND_REGEX.captures_iter(&value) {
-                        let res = x?;
+                        let res = x.map_err(|err| invalid_header(&header, &value, err))?;
                         let brand = res.get(1).map(|x| x.as_str()).unwrap_or_else(|| "");
                         let brand_version = res.get(2).map(|x| x.as_str()).unwrap_or_else(|| "");
                         full_version_list.push((brand.to_owned(), brand_version.to_owned()));
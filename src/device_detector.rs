@@ -1,16 +1,29 @@
-use anyhow::Result;
+use anyhow::{Context, Result};
 
 use serde::Serialize;
 
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::cache::{CacheKey, DetectionCache, DiskCache};
 use crate::client_hints::ClientHint;
+use crate::diagnostics::{self, Diagnostics};
+use crate::error::Error;
+use crate::options::DetectionOptions;
 use crate::parsers::client::ClientType;
+use crate::parsers::database::RegexDatabase;
 use crate::parsers::device::DeviceType;
+use crate::parsers::utils;
 use crate::parsers::{bot, client, device, oss};
+use crate::pool;
 
 #[cfg(feature = "cache")]
-use moka::sync::Cache;
+use crate::cache::MokaCache;
 
-pub use bot::Bot;
+pub use bot::{Bot, BotRef};
+pub use utils::RegexStats;
 
 // TODO we should Box KnownDevice as it is much larger than Bot
 #[allow(clippy::large_enum_variant)]
@@ -18,6 +31,81 @@ pub use bot::Bot;
 pub enum Detection {
     Known(KnownDevice),
     Bot(Bot),
+    /// A bot, but not which, see
+    /// [`DetectionOptions::discard_bot_information`].
+    AnonymousBot,
+}
+
+/// A [`Detection`] that borrows from the detector rather than copying into
+/// `String`s where it can, see [`DeviceDetector::parse_ref`]. Names, brands,
+/// engines and the like are borrowed from the regexes, while versions and
+/// models, which come out of the user agent, are owned.
+#[allow(clippy::large_enum_variant)]
+#[derive(Debug, Clone)]
+pub enum DetectionRef<'a> {
+    Known(KnownDeviceRef<'a>),
+    Bot(BotRef<'a>),
+    AnonymousBot,
+}
+
+impl<'a> DetectionRef<'a> {
+    pub fn get_bot(&self) -> Option<&BotRef<'a>> {
+        match self {
+            Self::Bot(bot) => Some(bot),
+            Self::Known(known) => known.bot.as_ref(),
+            Self::AnonymousBot => None,
+        }
+    }
+
+    pub fn get_known_device(&self) -> Option<&KnownDeviceRef<'a>> {
+        match self {
+            Self::Known(known) => Some(known),
+            _ => None,
+        }
+    }
+
+    /// Did we detect a bot? If not, then it is a known device.
+    pub fn is_bot(&self) -> bool {
+        match self {
+            Self::Known(known) => known.is_bot(),
+            Self::Bot(_) | Self::AnonymousBot => true,
+        }
+    }
+
+    pub fn to_owned(&self) -> Detection {
+        self.clone().into()
+    }
+
+    /// The same json as [`Detection::to_value`].
+    pub fn to_value(self) -> serde_json::Value {
+        match self {
+            Self::Known(known) => Detection::Known(known.into()).to_value(),
+            Self::Bot(bot) => serde_json::json!({
+                "bot": serde_json::to_value(bot).unwrap()
+            }),
+            Self::AnonymousBot => serde_json::json!({ "bot": true }),
+        }
+    }
+}
+
+impl From<DetectionRef<'_>> for Detection {
+    fn from(detection: DetectionRef<'_>) -> Self {
+        match detection {
+            DetectionRef::Known(known) => Detection::Known(known.into()),
+            DetectionRef::Bot(bot) => Detection::Bot(bot.into()),
+            DetectionRef::AnonymousBot => Detection::AnonymousBot,
+        }
+    }
+}
+
+impl From<Detection> for DetectionRef<'_> {
+    fn from(detection: Detection) -> Self {
+        match detection {
+            Detection::Known(known) => DetectionRef::Known(known.into()),
+            Detection::Bot(bot) => DetectionRef::Bot(bot.into()),
+            Detection::AnonymousBot => DetectionRef::AnonymousBot,
+        }
+    }
 }
 
 #[derive(Clone, Debug, Serialize)]
@@ -25,13 +113,63 @@ pub struct KnownDevice {
     pub client: Option<client::Client>,
     pub device: Option<device::Device>,
     pub os: Option<oss::OS>,
+    /// The bot that sent this user agent, only ever found with
+    /// [`DetectionOptions::detect_bot_devices`].
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bot: Option<Bot>,
+}
+
+/// A [`KnownDevice`] made of the borrowed forms of its parts, see
+/// [`DetectionRef`].
+#[derive(Clone, Debug, Serialize)]
+pub struct KnownDeviceRef<'a> {
+    pub client: Option<client::ClientRef<'a>>,
+    pub device: Option<device::DeviceRef<'a>>,
+    pub os: Option<oss::OSRef<'a>>,
+    /// The bot that sent this user agent, only ever found with
+    /// [`DetectionOptions::detect_bot_devices`].
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bot: Option<BotRef<'a>>,
+}
+
+impl KnownDeviceRef<'_> {
+    pub fn to_owned(&self) -> KnownDevice {
+        self.clone().into()
+    }
+
+    pub fn is_bot(&self) -> bool {
+        self.bot.is_some()
+    }
+}
+
+impl From<KnownDeviceRef<'_>> for KnownDevice {
+    fn from(known: KnownDeviceRef<'_>) -> Self {
+        KnownDevice {
+            client: known.client.map(client::Client::from),
+            device: known.device.map(device::Device::from),
+            os: known.os.map(oss::OS::from),
+            bot: known.bot.map(Bot::from),
+        }
+    }
+}
+
+impl From<KnownDevice> for KnownDeviceRef<'_> {
+    fn from(known: KnownDevice) -> Self {
+        KnownDeviceRef {
+            client: known.client.map(client::ClientRef::from),
+            device: known.device.map(device::DeviceRef::from),
+            os: known.os.map(oss::OSRef::from),
+            bot: known.bot.map(BotRef::from),
+        }
+    }
 }
 
 impl Detection {
     pub fn get_bot(&self) -> Option<&Bot> {
         match self {
             Self::Bot(bot) => Some(bot),
-            _ => None,
+            Self::Known(known) => known.bot.as_ref(),
+            Self::AnonymousBot => None,
         }
     }
 
@@ -44,7 +182,20 @@ impl Detection {
 
     /// Did we detect a bot? If not, then it is a known device.
     pub fn is_bot(&self) -> bool {
-        matches!(self, Self::Bot(_))
+        match self {
+            Self::Known(known) => known.is_bot(),
+            Self::Bot(_) | Self::AnonymousBot => true,
+        }
+    }
+
+    /// Roughly how many bytes this takes up, going by how long its json is.
+    pub fn size(&self) -> usize {
+        let json = match self {
+            Self::Known(known) => serde_json::to_vec(known),
+            Self::Bot(bot) => serde_json::to_vec(bot),
+            Self::AnonymousBot => Ok(Vec::new()),
+        };
+        std::mem::size_of::<Self>() + json.map_or(0, |json| json.len())
     }
     /// This is purely to aid in generating test cases, you should not rely on this for
     /// actual production usage. Only useful for normal stuff, not bots, etc.
@@ -226,8 +377,9 @@ impl Detection {
                     "wearable": known.is_wearable(),
                     "phablet": known.is_phablet(),
 
-                    // this should never have been emitted, is always false by this point.
-                    "robot": false,
+                    // this should never have been emitted, is only true with
+                    // DetectionOptions::detect_bot_devices.
+                    "robot": known.is_bot(),
 
                 });
 
@@ -240,6 +392,7 @@ impl Detection {
                     "bot": serde_json::to_value(bot).unwrap()
                 })
             }
+            Detection::AnonymousBot => serde_json::json!({ "bot": true }),
         }
     }
 }
@@ -278,7 +431,7 @@ impl KnownDevice {
         }
 
         if let Some(client) = &self.client {
-            if device::uses_mobile_browser(client) {
+            if client.uses_mobile_browser() {
                 return true;
             }
         }
@@ -298,7 +451,7 @@ impl KnownDevice {
     }
 
     pub fn is_bot(&self) -> bool {
-        false
+        self.bot.is_some()
     }
 
     pub fn is_pim(&self) -> bool {
@@ -537,43 +690,355 @@ impl KnownDevice {
     }
 }
 
+/// What [`DeviceDetector::warm_up`] did.
+#[derive(Clone, Copy, Debug)]
+pub struct WarmUp {
+    /// How many patterns there are in total.
+    pub patterns: usize,
+    /// How many of those had not been compiled yet.
+    pub compiled: usize,
+    pub elapsed: Duration,
+}
+
 // use std::alloc::System;
 
-#[cfg(feature = "cache")]
-type DetectionCache = Cache<String, Detection>;
+/// How well the cache is doing. All zeroes when caching is disabled.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct CacheStats {
+    pub hits: u64,
+    pub misses: u64,
+    /// Roughly how many detections are cached right now.
+    pub entries: u64,
+}
+
+#[derive(Debug, Default)]
+struct CacheCounters {
+    hits: AtomicU64,
+    misses: AtomicU64,
+}
 
-#[derive(Clone)]
+#[derive(Clone, Debug)]
 pub struct DeviceDetector {
-    #[cfg(feature = "cache")]
-    caching: bool,
-    #[cfg(feature = "cache")]
-    cache: DetectionCache,
+    database: Arc<RegexDatabase>,
+    diagnostics: Arc<Diagnostics>,
+    cache: Option<Arc<dyn DetectionCache>>,
+    cache_counters: Arc<CacheCounters>,
+    options: DetectionOptions,
 }
 
-impl DeviceDetector {
-    #[cfg(not(feature = "cache"))]
-    #[allow(clippy::new_without_default)]
-    pub fn new() -> Self {
-        Self {}
+/// Builds a [`DeviceDetector`] from something other than the defaults, such as
+/// regexes loaded from disk at runtime.
+#[derive(Clone, Debug, Default)]
+pub struct DeviceDetectorBuilder {
+    regex_dir: Option<PathBuf>,
+    diagnostics: Option<Arc<Diagnostics>>,
+    cache: Option<Arc<dyn DetectionCache>>,
+    disk_cache: Option<PathBuf>,
+    options: DetectionOptions,
+}
+
+impl DeviceDetectorBuilder {
+    /// Load the regexes from a directory laid out like `regexes/` in this
+    /// repository (or upstream), instead of those compiled into the binary.
+    pub fn regex_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.regex_dir = Some(dir.into());
+        self
     }
 
+    /// Record into `diagnostics` rather than a fresh one. It can be shared
+    /// between detectors, to keep counting across a reload for instance.
+    pub fn diagnostics(mut self, diagnostics: Arc<Diagnostics>) -> Self {
+        self.diagnostics = Some(diagnostics);
+        self
+    }
+
+    /// Cache up to `entries` detections in memory, see [`MokaCache`].
     #[cfg(feature = "cache")]
+    pub fn cache(self, entries: u64) -> Self {
+        self.detection_cache(Arc::new(MokaCache::new(entries)))
+    }
+
+    /// Cache detections in `cache`, which can be shared between detectors,
+    /// as long as they use the same regexes.
+    pub fn detection_cache(mut self, cache: Arc<dyn DetectionCache>) -> Self {
+        self.cache = Some(cache);
+        self
+    }
+
+    /// Cache detections in a file in `dir`, so they are kept from one run to
+    /// the next, see [`DiskCache`]. This takes the place of any other cache.
+    pub fn disk_cache(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.disk_cache = Some(dir.into());
+        self
+    }
+
+    /// Report detections according to `options`, see [`DetectionOptions`].
+    pub fn options(mut self, options: DetectionOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    #[cfg(feature = "build-binary")]
+    pub(crate) fn get_regex_dir(&self) -> Option<&Path> {
+        self.regex_dir.as_deref()
+    }
+
+    /// Swaps the cache for an empty one like it, see
+    /// [`DetectionCache::empty`], so a detector with reloaded regexes never
+    /// sees what the old ones detected.
+    ///
+    /// A disk cache is left as it is, as [`build`](Self::build) opens the file
+    /// for the version of the regexes it loads. That is only the file the old
+    /// detector had when the regexes are unchanged, and then its detections
+    /// are still good.
+    #[cfg(feature = "build-binary")]
+    pub(crate) fn empty_cache(mut self) -> Self {
+        self.cache = self.cache.map(|cache| cache.empty());
+        self
+    }
+
+    pub fn build(self) -> Result<DeviceDetector> {
+        let database = match &self.regex_dir {
+            Some(dir) => Arc::new(RegexDatabase::from_dir(dir)?),
+            None => RegexDatabase::embedded(),
+        };
+
+        let cache = match &self.disk_cache {
+            Some(dir) => {
+                let cache = DiskCache::open(dir, database.version())
+                    .with_context(|| format!("opening the cache in {}", dir.display()))?;
+                Some(cache as Arc<dyn DetectionCache>)
+            }
+            None => self.cache,
+        };
+
+        Ok(DeviceDetector {
+            database,
+            diagnostics: self.diagnostics.unwrap_or_default(),
+            cache,
+            cache_counters: Default::default(),
+            options: self.options,
+        })
+    }
+}
+
+impl DeviceDetector {
     #[allow(clippy::new_without_default)]
     pub fn new() -> Self {
         Self {
-            caching: false,
-            cache: Cache::new(0),
+            database: RegexDatabase::embedded(),
+            diagnostics: Default::default(),
+            cache: None,
+            cache_counters: Default::default(),
+            options: Default::default(),
         }
     }
 
     #[cfg(feature = "cache")]
     pub fn new_with_cache(entries: u64) -> Self {
         Self {
-            caching: true,
-            cache: Cache::new(entries),
+            database: RegexDatabase::embedded(),
+            diagnostics: Default::default(),
+            cache: Some(Arc::new(MokaCache::new(entries))),
+            cache_counters: Default::default(),
+            options: Default::default(),
+        }
+    }
+
+    pub fn builder() -> DeviceDetectorBuilder {
+        DeviceDetectorBuilder::default()
+    }
+
+    /// Loads the regexes from `dir` at runtime rather than using the ones
+    /// compiled into the binary. `dir` must have the same layout as the
+    /// `regexes/` directory of this repository.
+    pub fn from_regex_dir(dir: impl AsRef<Path>) -> Result<Self> {
+        Self::builder().regex_dir(dir.as_ref()).build()
+    }
+
+    /// How many regexes have been compiled by each engine so far. This is
+    /// for the whole process, not just this detector.
+    pub fn regex_stats() -> RegexStats {
+        utils::regex_stats()
+    }
+
+    /// What has gone wrong during detection so far, such as regexes that hit
+    /// the backtrack limit.
+    pub fn diagnostics(&self) -> &Arc<Diagnostics> {
+        &self.diagnostics
+    }
+
+    pub fn options(&self) -> &DetectionOptions {
+        &self.options
+    }
+
+    /// The same detector, sharing its regexes and cache, but reporting
+    /// detections according to `options` instead.
+    pub fn with_options(&self, options: DetectionOptions) -> Self {
+        Self {
+            options,
+            ..self.clone()
+        }
+    }
+
+    /// Compiles every regex now, across `threads` threads, instead of as each
+    /// is first needed. Otherwise the first detections after startup are
+    /// slow, as they end up compiling thousands of patterns between them.
+    pub fn warm_up(&self, threads: usize) -> Result<WarmUp> {
+        let started = Instant::now();
+
+        let regexes: Vec<_> = self.database.regexes().map(|(_, regex)| regex).collect();
+
+        // hand out one regex at a time, as some take far longer than others
+        let next = AtomicUsize::new(0);
+        let compiled = AtomicUsize::new(0);
+        let error = Mutex::new(None);
+
+        std::thread::scope(|scope| {
+            for _ in 0..threads.max(1) {
+                scope.spawn(|| {
+                    while let Some(regex) = regexes.get(next.fetch_add(1, Ordering::Relaxed)) {
+                        match regex.compile() {
+                            Ok(true) => {
+                                compiled.fetch_add(1, Ordering::Relaxed);
+                            }
+                            Ok(false) => (),
+                            Err(err) => {
+                                let err = err.context(format!("compiling {}", regex.pattern));
+                                error.lock().unwrap().get_or_insert(err);
+                                return;
+                            }
+                        }
+                    }
+                });
+            }
+
+            scope.spawn(|| self.database.build_prefilters());
+        });
+
+        if let Some(err) = error.into_inner().unwrap() {
+            return Err(err);
+        }
+
+        // the regexes built into the parsers themselves are compiled by
+        // running through them with a few common user agents.
+        for ua in [
+            "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/120.0.0.0 Safari/537.36",
+            "Mozilla/5.0 (Linux; Android 13; SM-S911B) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/120.0.0.0 Mobile Safari/537.36",
+            "Mozilla/5.0 (iPhone; CPU iPhone OS 17_1 like Mac OS X) AppleWebKit/605.1.15 (KHTML, like Gecko) Version/17.1 Mobile/15E148 Safari/604.1",
+        ] {
+            self.parse(ua, None)
+                .with_context(|| format!("warming up with {}", ua))?;
+        }
+
+        Ok(WarmUp {
+            patterns: regexes.len(),
+            compiled: compiled.into_inner(),
+            elapsed: started.elapsed(),
+        })
+    }
+
+    /// Detects many user agents at once, each with optional headers, spread
+    /// across up to `threads` threads, the calling one included. The others
+    /// come from a pool kept for the life of the process, rather than being
+    /// spawned for every batch. The results are in the same order as
+    /// `requests`, and one failing doesn't affect the others.
+    #[allow(clippy::type_complexity)]
+    pub fn parse_batch<S>(
+        &self,
+        requests: &[(S, Option<Vec<(String, String)>>)],
+        threads: usize,
+    ) -> Vec<Result<Detection, Error>>
+    where
+        S: AsRef<str> + Sync,
+    {
+        // the pool's threads outlive this call, so they need their own copy
+        let requests: Arc<[(String, Option<Vec<(String, String)>>)]> = requests
+            .iter()
+            .map(|(ua, headers)| (ua.as_ref().to_owned(), headers.clone()))
+            .collect();
+        let (results, received) = std::sync::mpsc::channel();
+
+        let work = {
+            let detector = self.clone();
+            let requests = requests.clone();
+            let next = Arc::new(AtomicUsize::new(0));
+            move || loop {
+                let i = next.fetch_add(1, Ordering::Relaxed);
+                let Some((ua, headers)) = requests.get(i) else {
+                    break;
+                };
+                let _ = results.send((i, detector.parse(ua, headers.clone())));
+            }
+        };
+
+        // whichever of the pool's threads are busy with other batches can
+        // join in late, or not at all, the calling thread keeps going anyway.
+        let helpers = threads.max(1).min(requests.len()).saturating_sub(1);
+        for _ in 0..helpers {
+            pool::pool().execute(helpers, work.clone());
+        }
+        work();
+        // the helpers drop their senders as they finish, this one has to go
+        // too, or a result lost to a panicking helper is waited on forever.
+        drop(work);
+
+        let mut results: Vec<(usize, Result<Detection, Error>)> =
+            received.iter().take(requests.len()).collect();
+        assert_eq!(results.len(), requests.len(), "batch worker panicked");
+
+        results.sort_by_key(|(i, _)| *i);
+        results.into_iter().map(|(_, result)| result).collect()
+    }
+
+    /// Identifies the regexes this detector uses, and changes whenever any of
+    /// them do. Detections cached under one version can't be trusted under
+    /// another.
+    pub fn database_version(&self) -> u64 {
+        self.database.version()
+    }
+
+    /// Each file of the regexes, along with everything parsed from it as
+    /// `{:#?}` prints it, so that detectors whose regexes were loaded from
+    /// different places can be compared file by file. Only meant for tests.
+    #[doc(hidden)]
+    pub fn regex_files(&self) -> Vec<(&'static str, String)> {
+        self.database
+            .files()
+            .into_iter()
+            .map(|(path, list)| (path, format!("{:#?}", list)))
+            .collect()
+    }
+
+    pub fn cache_stats(&self) -> CacheStats {
+        CacheStats {
+            hits: self.cache_counters.hits.load(Ordering::Relaxed),
+            misses: self.cache_counters.misses.load(Ordering::Relaxed),
+            entries: self.cache.as_ref().map_or(0, |cache| cache.entries()),
+        }
+    }
+
+    /// Whether detections made by `other` end up in the same cache as ours.
+    #[cfg(feature = "build-binary")]
+    pub(crate) fn shares_cache_with(&self, other: &DeviceDetector) -> bool {
+        match (&self.cache, &other.cache) {
+            (Some(ours), Some(theirs)) => std::ptr::addr_eq(Arc::as_ptr(ours), Arc::as_ptr(theirs)),
+            _ => false,
+        }
+    }
+
+    /// Drops every cached detection. Does nothing when caching is disabled.
+    pub fn clear_cache(&self) {
+        if let Some(cache) = &self.cache {
+            cache.clear();
         }
     }
-    pub fn parse(&self, ua: &str, headers: Option<Vec<(String, String)>>) -> Result<Detection> {
+
+    pub fn parse(
+        &self,
+        ua: &str,
+        headers: Option<Vec<(String, String)>>,
+    ) -> Result<Detection, Error> {
         let client_hints = match headers {
             Some(headers) => Some(ClientHint::from_headers(headers)?),
             None => None,
@@ -585,41 +1050,101 @@ impl DeviceDetector {
         &self,
         ua: &str,
         client_hints: Option<ClientHint>,
-    ) -> Result<Detection> {
-        let parse = || {
-            if let Some(bot) = bot::lookup_bot(ua)? {
-                return Ok(Detection::Bot(bot));
-            }
+    ) -> Result<Detection, Error> {
+        self.parse_client_hints_ref(ua, client_hints)
+            .map(Detection::from)
+    }
 
-            let os = oss::lookup(ua, client_hints.as_ref())?;
+    /// Like [`DeviceDetector::parse`], but borrows what it can of the
+    /// detection from this detector instead of copying it, which saves
+    /// allocating for fields that are never looked at.
+    pub fn parse_ref(
+        &self,
+        ua: &str,
+        headers: Option<Vec<(String, String)>>,
+    ) -> Result<DetectionRef<'_>, Error> {
+        let client_hints = match headers {
+            Some(headers) => Some(ClientHint::from_headers(headers)?),
+            None => None,
+        };
+        self.parse_client_hints_ref(ua, client_hints)
+    }
 
-            let client = client::lookup(ua, client_hints.as_ref())?;
+    pub fn parse_client_hints_ref(
+        &self,
+        ua: &str,
+        client_hints: Option<ClientHint>,
+    ) -> Result<DetectionRef<'_>, Error> {
+        let mut detection = self.detect_cached(ua, client_hints)?;
+        self.options.apply(&mut detection);
+        Ok(detection)
+    }
 
-            let device = device::lookup(ua, client.as_ref(), client_hints.as_ref(), os.as_ref())?;
+    fn detect_cached(
+        &self,
+        ua: &str,
+        client_hints: Option<ClientHint>,
+    ) -> Result<DetectionRef<'_>, Error> {
+        if let Some(cache) = &self.cache {
+            let key = CacheKey {
+                user_agent: ua.to_owned(),
+                client_hints,
+                skip_bot_detection: self.options.skip_bot_detection,
+                detect_bot_devices: self.options.detect_bot_devices,
+            };
 
-            let known = Detection::Known(KnownDevice { client, device, os });
+            if let Some(res) = cache.get(&key) {
+                self.cache_counters.hits.fetch_add(1, Ordering::Relaxed);
+                return Ok(res.into());
+            };
+            self.cache_counters.misses.fetch_add(1, Ordering::Relaxed);
 
-            Ok::<_, anyhow::Error>(known)
-        };
+            let known = self.detect(ua, key.client_hints.as_ref())?;
 
-        #[cfg(feature = "cache")]
-        {
-            if !self.caching {
-                return Ok(parse()?);
-            }
+            cache.insert(key, known.to_owned());
+
+            return Ok(known);
+        }
+
+        self.detect(ua, client_hints.as_ref())
+    }
 
-            if let Some(res) = self.cache.get(ua) {
-                return Ok(res);
+    fn detect(
+        &self,
+        ua: &str,
+        client_hints: Option<&ClientHint>,
+    ) -> Result<DetectionRef<'_>, Error> {
+        let db = &*self.database;
+
+        diagnostics::scope(&self.diagnostics, &self.database, ua, || {
+            let bot = if self.options.skip_bot_detection {
+                None
+            } else {
+                bot::lookup_bot(db, ua)?
             };
 
-            let known = parse()?;
+            let bot = match bot {
+                Some(bot) if !self.options.detect_bot_devices => {
+                    return Ok(DetectionRef::Bot(bot));
+                }
+                bot => bot,
+            };
 
-            self.cache.insert(ua.to_owned(), known.clone());
+            let os = oss::lookup(db, ua, client_hints)?;
 
-            Ok(known)
-        }
+            let client = client::lookup(db, ua, client_hints)?;
+
+            let device = device::lookup(db, ua, client.as_ref(), client_hints, os.as_ref())?;
 
-        #[cfg(not(feature = "cache"))]
-        parse()
+            let known = DetectionRef::Known(KnownDeviceRef {
+                client,
+                device,
+                os,
+                bot,
+            });
+
+            Ok::<_, anyhow::Error>(known)
+        })
+        .map_err(Error::from)
     }
 }
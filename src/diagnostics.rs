@@ -0,0 +1,174 @@
+use serde::Serialize;
+
+use std::cell::Cell;
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use crate::parsers::database::RegexDatabase;
+
+/// A regex that gave up on a user agent, having hit fancy_regex's backtrack
+/// limit, rather than either matching or not. The detection carries on as if
+/// it didn't match, which may well be wrong.
+#[derive(Clone, Debug, Serialize)]
+pub struct RegexFailure {
+    /// The regex file the pattern came from, relative to `regexes/`, or
+    /// `builtin` for those that are part of the code.
+    pub list: &'static str,
+    pub pattern: String,
+    pub user_agent: String,
+}
+
+/// How many times a single pattern has failed.
+#[derive(Clone, Debug, Serialize)]
+pub struct RegexFailureCount {
+    pub list: &'static str,
+    pub pattern: String,
+    pub count: u64,
+    /// The most recent user agent it failed on.
+    pub user_agent: String,
+}
+
+type Callback = Box<dyn Fn(&RegexFailure) + Send + Sync>;
+
+/// Records what went wrong during detection, so bad upstream regexes can be
+/// found and reported. Every detector has one, see
+/// [`DeviceDetector::diagnostics`](crate::device_detector::DeviceDetector::diagnostics).
+#[derive(Default)]
+pub struct Diagnostics {
+    regex_failures: Mutex<HashMap<(&'static str, String), RegexFailureCount>>,
+    callback: Option<Callback>,
+}
+
+impl std::fmt::Debug for Diagnostics {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Diagnostics")
+            .field("regex_failures", &self.regex_failures.lock().unwrap().len())
+            .finish()
+    }
+}
+
+impl Diagnostics {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Also call `callback` on every failure, as it happens.
+    pub fn with_callback(callback: impl Fn(&RegexFailure) + Send + Sync + 'static) -> Self {
+        Self {
+            callback: Some(Box::new(callback)),
+            ..Self::default()
+        }
+    }
+
+    /// Every pattern that has failed so far, most failures first.
+    pub fn regex_failures(&self) -> Vec<RegexFailureCount> {
+        let mut failures: Vec<_> = self
+            .regex_failures
+            .lock()
+            .unwrap()
+            .values()
+            .cloned()
+            .collect();
+
+        failures.sort_by(|a, b| {
+            b.count
+                .cmp(&a.count)
+                .then_with(|| a.list.cmp(b.list))
+                .then_with(|| a.pattern.cmp(&b.pattern))
+        });
+        failures
+    }
+
+    fn record(&self, failure: RegexFailure) {
+        {
+            let mut failures = self.regex_failures.lock().unwrap();
+            let count = failures
+                .entry((failure.list, failure.pattern.clone()))
+                .or_insert_with(|| RegexFailureCount {
+                    list: failure.list,
+                    pattern: failure.pattern.clone(),
+                    count: 0,
+                    user_agent: String::new(),
+                });
+            count.count += 1;
+            count.user_agent.clone_from(&failure.user_agent);
+        }
+
+        if let Some(callback) = &self.callback {
+            callback(&failure);
+        }
+    }
+}
+
+// Regexes are matched a long way down from the detector, and failures are
+// rare, so rather than pass all of this down to every regex, the detector
+// leaves it here for the duration of each detection. It is only borrowed, as
+// copying the user agent for every detection would cost far more than the
+// failures ever do.
+#[derive(Clone, Copy)]
+struct Context {
+    diagnostics: *const Diagnostics,
+    database: *const RegexDatabase,
+    user_agent: *const str,
+}
+
+thread_local! {
+    static CONTEXT: Cell<Option<Context>> = const { Cell::new(None) };
+}
+
+/// Runs `f` with any regex failures on this thread recorded into
+/// `diagnostics`.
+pub(crate) fn scope<T>(
+    diagnostics: &Diagnostics,
+    database: &RegexDatabase,
+    user_agent: &str,
+    f: impl FnOnce() -> T,
+) -> T {
+    struct Restore(Option<Context>);
+
+    impl Drop for Restore {
+        fn drop(&mut self) {
+            CONTEXT.set(self.0);
+        }
+    }
+
+    let _restore = Restore(CONTEXT.replace(Some(Context {
+        diagnostics,
+        database,
+        user_agent,
+    })));
+
+    f()
+}
+
+/// Called when `pattern` hits a runtime error and is treated as not matching.
+pub(crate) fn regex_failed(pattern: &str) {
+    let Some(context) = CONTEXT.get() else {
+        return;
+    };
+
+    // SAFETY: the context is only set while `scope` runs, which borrows all
+    // of it for at least that long, and puts the previous one back, even on
+    // a panic, before it returns.
+    let (diagnostics, database, user_agent) = unsafe {
+        (
+            &*context.diagnostics,
+            &*context.database,
+            &*context.user_agent,
+        )
+    };
+
+    let list = database
+        .regexes()
+        .find(|(_, regex)| regex.pattern == pattern)
+        .map(|(list, _)| list)
+        .unwrap_or("builtin");
+
+    // the callback could very well use a detector itself, which is fine, as
+    // nothing here is borrowed from the thread local.
+    diagnostics.record(RegexFailure {
+        list,
+        pattern: pattern.to_owned(),
+        user_agent: user_agent.to_owned(),
+    });
+}
@@ -0,0 +1,100 @@
+use serde_json::Value;
+
+/// Why a detection failed.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum Error {
+    /// A regex could not be compiled, usually because it uses syntax neither
+    /// regex engine supports.
+    InvalidRegex { pattern: String, message: String },
+
+    /// A regex failed while matching. Hitting the backtrack limit is not one
+    /// of these, that is treated as not matching, see
+    /// [`Diagnostics`](crate::diagnostics::Diagnostics).
+    RegexRuntime { pattern: String, message: String },
+
+    /// A header could not be understood as a client hint.
+    InvalidHeader {
+        name: String,
+        value: String,
+        message: String,
+    },
+
+    /// The user agent or a header was not valid utf-8.
+    InvalidUtf8,
+
+    /// Anything else.
+    Other(anyhow::Error),
+}
+
+impl Error {
+    /// A short, stable name for the kind of error, such as `invalid_header`.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Self::InvalidRegex { .. } => "invalid_regex",
+            Self::RegexRuntime { .. } => "regex_runtime",
+            Self::InvalidHeader { .. } => "invalid_header",
+            Self::InvalidUtf8 => "invalid_utf8",
+            Self::Other(_) => "other",
+        }
+    }
+
+    /// The json an error is reported as, in place of a detection.
+    pub fn to_value(&self) -> Value {
+        serde_json::json!({
+            "error": {
+                "kind": self.kind(),
+                "message": self.to_string(),
+            }
+        })
+    }
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::InvalidRegex { pattern, message } => {
+                write!(f, "invalid regex {}: {}", pattern, message)
+            }
+            Self::RegexRuntime { pattern, message } => {
+                write!(f, "regex {} failed: {}", pattern, message)
+            }
+            Self::InvalidHeader {
+                name,
+                value,
+                message,
+            } => write!(f, "invalid header {}: {}: {}", name, value, message),
+            Self::InvalidUtf8 => write!(f, "invalid utf-8"),
+            Self::Other(err) => write!(f, "{:#}", err),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Other(err) => Some(err.as_ref()),
+            _ => None,
+        }
+    }
+}
+
+// Internally everything is an anyhow::Error, with these wrapped up inside
+// wherever they happened.
+impl From<anyhow::Error> for Error {
+    fn from(err: anyhow::Error) -> Self {
+        err.downcast::<Error>().unwrap_or_else(Self::Other)
+    }
+}
+
+impl From<std::str::Utf8Error> for Error {
+    fn from(_: std::str::Utf8Error) -> Self {
+        Self::InvalidUtf8
+    }
+}
+
+impl From<std::string::FromUtf8Error> for Error {
+    fn from(_: std::string::FromUtf8Error) -> Self {
+        Self::InvalidUtf8
+    }
+}
@@ -59,6 +59,20 @@ pub extern "C" fn rdd_device_detector_new(cache_size: u64) -> *mut RDDDeviceDete
     Box::into_raw(Box::new(RDDDeviceDetector::new(cache_size)))
 }
 
+/// Have bots report the client, device and os they claim to be as well, so
+/// `rdd_client`, `rdd_device` and `rdd_os` can be used alongside `rdd_bot`.
+#[no_mangle]
+pub unsafe extern "C" fn rdd_device_detector_detect_bot_devices(
+    rdd: *mut RDDDeviceDetector,
+    enabled: bool,
+) {
+    let rdd = unsafe { &mut *rdd };
+
+    let mut options = rdd.dd.options().clone();
+    options.detect_bot_devices = enabled;
+    rdd.dd = rdd.dd.with_options(options);
+}
+
 #[no_mangle]
 pub unsafe extern "C" fn rdd_lookup(
     rdd: *const RDDDeviceDetector,
@@ -115,6 +129,18 @@ pub unsafe extern "C" fn rdd_client_name(client: *const RDDClient) -> *mut c_cha
         .unwrap_or(null_mut())
 }
 
+#[no_mangle]
+pub unsafe extern "C" fn rdd_client_short_name(client: *const RDDClient) -> *mut c_char {
+    let client = unsafe { &*client };
+
+    client
+        .client
+        .as_ref()
+        .and_then(|client| client.short_name.as_deref())
+        .map(|short_name| CString::new(short_name).unwrap().into_raw())
+        .unwrap_or(null_mut())
+}
+
 // TODO this could just be a number, which could be converted into a static str.
 #[no_mangle]
 pub unsafe extern "C" fn rdd_client_type(client: *const RDDClient) -> *mut c_char {
@@ -191,6 +217,18 @@ pub unsafe extern "C" fn rdd_device_brand(device: *const RDDDevice) -> *mut c_ch
         .unwrap_or(null_mut())
 }
 
+#[no_mangle]
+pub unsafe extern "C" fn rdd_device_short_name(device: *const RDDDevice) -> *mut c_char {
+    let device = unsafe { &*device };
+
+    device
+        .device
+        .as_ref()
+        .and_then(|d| d.short_name.as_ref())
+        .map(|s| CString::new(s.as_str()).unwrap().into_raw())
+        .unwrap_or(null_mut())
+}
+
 #[no_mangle]
 pub unsafe extern "C" fn rdd_device_model(device: *const RDDDevice) -> *mut c_char {
     let device = unsafe { &*device };
@@ -236,6 +274,16 @@ pub unsafe extern "C" fn rdd_os_name(os: *const RDDOS) -> *mut c_char {
         .unwrap_or(null_mut())
 }
 #[no_mangle]
+pub unsafe extern "C" fn rdd_os_short_name(os: *const RDDOS) -> *mut c_char {
+    let os = unsafe { &*os };
+
+    os.os
+        .as_ref()
+        .and_then(|os| os.short_name.as_deref())
+        .map(|short_name| CString::new(short_name).unwrap().into_raw())
+        .unwrap_or(null_mut())
+}
+#[no_mangle]
 pub unsafe extern "C" fn rdd_os_version(os: *const RDDOS) -> *mut c_char {
     let os = unsafe { &*os };
 
@@ -283,12 +331,13 @@ pub unsafe extern "C" fn rdd_os_family(os: *const RDDOS) -> *mut c_char {
 pub unsafe extern "C" fn rdd_bot<'a>(rdd: *const RDDDetection) -> *const RDDBot<'a> {
     let rdd = unsafe { &*rdd };
 
-    match &rdd.dt {
-        Some(Detection::Bot(bot)) => {
+    // a known device has a bot too with detect_bot_devices
+    match rdd.dt.as_ref().and_then(Detection::get_bot) {
+        Some(bot) => {
             let rdd = RDDBot { bot: Some(bot) };
             Box::into_raw(Box::new(rdd))
         }
-        _ => null(),
+        None => null(),
     }
 }
 
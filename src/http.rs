@@ -1,75 +1,1109 @@
-use anyhow::Result;
+use anyhow::{anyhow, Result};
 
+use http_body_util::channel::{Channel, Sender};
+use http_body_util::combinators::BoxBody;
+use http_body_util::{BodyExt, Full};
+use hyper::body::{Bytes, Incoming};
+use hyper::header::{
+    HeaderMap, AUTHORIZATION, CONTENT_LENGTH, CONTENT_TYPE, USER_AGENT, WWW_AUTHENTICATE,
+};
 use hyper::http::StatusCode;
-use hyper::service::{make_service_fn, service_fn};
-use hyper::{Body, Method, Request, Response, Server};
-use std::convert::Infallible;
+use hyper::service::service_fn;
+use hyper::{Method, Request, Response};
+use hyper_util::rt::{TokioExecutor, TokioIo, TokioTimer};
+use hyper_util::server::conn::auto;
+use hyper_util::server::graceful::GracefulShutdown;
+use indexmap::IndexMap;
+use serde::Deserialize;
 use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio::net::TcpListener;
 
-use crate::device_detector::DeviceDetector;
-use std::sync::Arc;
+use crate::device_detector::{DeviceDetector, DeviceDetectorBuilder};
+use crate::error::Error;
+use std::sync::{Arc, RwLock};
+use std::time::Instant;
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
 
-async fn serve_request(
-    req: Request<Body>,
+mod metrics;
+
+use metrics::Metrics;
+
+/// Every response body, whether sent all at once or streamed.
+type Body = BoxBody<Bytes, anyhow::Error>;
+
+fn full(body: impl Into<Bytes>) -> Body {
+    Full::new(body.into())
+        .map_err(|never| match never {})
+        .boxed()
+}
+
+/// Where the server listens.
+#[derive(Clone, Debug)]
+pub enum Listen {
+    Tcp(SocketAddr),
+
+    /// A unix domain socket at this path. A stale socket left behind by an
+    /// earlier run is replaced, and the socket is removed again on shutdown.
+    #[cfg(unix)]
+    Unix(PathBuf),
+}
+
+impl From<SocketAddr> for Listen {
+    fn from(addr: SocketAddr) -> Self {
+        Self::Tcp(addr)
+    }
+}
+
+impl std::fmt::Display for Listen {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Tcp(addr) => write!(f, "{}", addr),
+            #[cfg(unix)]
+            Self::Unix(path) => write!(f, "{}", path.display()),
+        }
+    }
+}
+
+/// How the server replaces its detector with a freshly loaded one. A reload
+/// can be requested with SIGHUP, by watching the regex directory for
+/// changes, or with `POST /reload` if a `token` is set.
+#[derive(Clone, Debug)]
+pub struct Reload {
+    /// Builds the replacement detector. This should be the same builder the
+    /// running detector was built with.
+    pub builder: DeviceDetectorBuilder,
+
+    /// If set, poll the builder's regex directory this often and reload
+    /// whenever anything in it changes.
+    pub watch_interval: Option<Duration>,
+
+    /// User agents which a new detector must parse without error before it
+    /// is allowed to replace the running one.
+    pub smoke_corpus: Vec<String>,
+
+    /// If set, compile every regex of a new detector, with this many threads,
+    /// before it replaces the running one. See [`DeviceDetector::warm_up`].
+    pub warm_up_threads: Option<usize>,
+
+    /// If set, `POST /reload` is enabled, for requests which carry it as
+    /// `Authorization: Bearer <token>`. Anyone else gets a 401.
+    pub token: Option<String>,
+}
+
+/// Limits on what a single request may take up, so that one misbehaving
+/// caller can't take the server down for everyone else.
+#[derive(Clone, Debug)]
+pub struct Limits {
+    /// The largest request body accepted, in bytes, beyond which the request
+    /// gets a 413. Newline delimited batches are streamed rather than held in
+    /// memory, so for those it applies to each line instead.
+    pub max_body_bytes: usize,
+
+    /// How long a request may take before it gets a 408, or for batches,
+    /// before results start streaming back. Detections already under way
+    /// can't be interrupted, but they do still count towards
+    /// `max_concurrent_requests` until they finish. Reloads take as long as
+    /// they take.
+    pub request_timeout: Duration,
+
+    /// How many requests may be handled at once. Any more get a 503 straight
+    /// away. `/health`, `/metrics` and `/reload` are not counted.
+    pub max_concurrent_requests: usize,
+
+    /// How long a client has to send the request headers before the
+    /// connection is closed. This includes waiting for the next request on a
+    /// kept alive connection, so it is also how long an idle connection is
+    /// kept open.
+    pub header_read_timeout: Duration,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Self {
+            max_body_bytes: 16 * 1024 * 1024,
+            request_timeout: Duration::from_secs(30),
+            max_concurrent_requests: 1024,
+            header_read_timeout: Duration::from_secs(10),
+        }
+    }
+}
+
+/// Everything about how the server runs, other than where it listens.
+#[derive(Clone, Debug)]
+pub struct Options {
+    /// If set, the detector can be replaced while running, see [`Reload`].
+    pub reload: Option<Reload>,
+    pub limits: Limits,
+
+    /// Whether http/1 connections are kept open between requests.
+    pub keep_alive: bool,
+
+    /// On SIGTERM or ctrl-c, the server stops accepting connections and
+    /// waits this long for requests in flight to finish before giving up on
+    /// them.
+    pub shutdown_timeout: Duration,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Self {
+            reload: None,
+            limits: Limits::default(),
+            keep_alive: true,
+            shutdown_timeout: Duration::from_secs(30),
+        }
+    }
+}
+
+/// Returned when a request body is larger than [`Limits::max_body_bytes`].
+#[derive(Debug)]
+struct PayloadTooLarge;
+
+impl std::fmt::Display for PayloadTooLarge {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "request body is too large")
+    }
+}
+
+impl std::error::Error for PayloadTooLarge {}
+
+/// Held for as long as a request is being worked on, including any
+/// detection still running after the request itself timed out.
+type Permit = Arc<OwnedSemaphorePermit>;
+
+/// Returned when a reload is requested while another one is still running.
+#[derive(Debug)]
+struct ReloadInProgress;
+
+impl std::fmt::Display for ReloadInProgress {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "a reload is already in progress")
+    }
+}
+
+impl std::error::Error for ReloadInProgress {}
+
+struct State {
+    detector: RwLock<Arc<DeviceDetector>>,
+    reload: Option<Reload>,
+    reloading: tokio::sync::Mutex<()>,
+    limits: Limits,
+    requests: Arc<Semaphore>,
+    metrics: Arc<Metrics>,
+}
+
+impl State {
+    fn detector(&self) -> Arc<DeviceDetector> {
+        self.detector.read().unwrap().clone()
+    }
+
+    /// Builds and validates a new detector off of the async runtime, and only
+    /// swaps it in if that succeeded. Requests in flight finish with the
+    /// detector they started with. This runs as a task of its own, so that it
+    /// still finishes if whoever asked for it stops waiting.
+    async fn reload(self: &Arc<Self>) -> Result<()> {
+        let state = self.clone();
+        tokio::spawn(async move { state.load_and_swap().await })
+            .await
+            .map_err(|err| anyhow!("reloading panicked: {}", err))?
+    }
+
+    async fn load_and_swap(&self) -> Result<()> {
+        let reload = self
+            .reload
+            .clone()
+            .ok_or_else(|| anyhow!("reloading is not enabled"))?;
+
+        let _reloading = self.reloading.try_lock().map_err(|_| ReloadInProgress)?;
+
+        // keep counting regex failures across reloads
+        let diagnostics = self.detector().diagnostics().clone();
+
+        let detector = tokio::task::spawn_blocking(move || {
+            let detector = reload
+                .builder
+                .empty_cache()
+                .diagnostics(diagnostics)
+                .build()?;
+            if let Some(threads) = reload.warm_up_threads {
+                detector.warm_up(threads)?;
+            }
+            smoke_test(&detector, &reload.smoke_corpus)?;
+            Ok::<_, anyhow::Error>(detector)
+        })
+        .await
+        .map_err(|err| anyhow!("loading regexes panicked: {}", err))??;
+
+        let new = Arc::new(detector);
+        let old = std::mem::replace(&mut *self.detector.write().unwrap(), new.clone());
+        // usually the new detector has a cache of its own, and this only frees
+        // the old one's memory, in case `reload.builder` still holds on to it.
+        // A disk cache is shared when the regexes are unchanged though, and is
+        // then just as good for the new detector.
+        if !new.shares_cache_with(&old) || new.database_version() != old.database_version() {
+            old.clear_cache();
+        }
+
+        eprintln!("Reloaded regexes");
+        Ok(())
+    }
+}
+
+/// A handful of user agents whose detection has been stable for years. If a
+/// new set of regexes gets any of these wrong, something is badly broken.
+fn smoke_test(detector: &DeviceDetector, corpus: &[String]) -> Result<()> {
+    let googlebot = "Googlebot/2.1 (+http://www.google.com/bot.html)";
+    if !detector.parse(googlebot, None)?.is_bot() {
+        return Err(anyhow!(
+            "smoke test: {} was not detected as a bot",
+            googlebot
+        ));
+    }
+
+    let chrome = "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/120.0.0.0 Safari/537.36";
+    let detection = detector.parse(chrome, None)?;
+    let known = detection.get_known_device();
+    if known
+        .and_then(|x| x.client.as_ref())
+        .map(|x| x.name.as_str())
+        != Some("Chrome")
+        || known.and_then(|x| x.os.as_ref()).map(|x| x.name.as_str()) != Some("Windows")
+    {
+        return Err(anyhow!(
+            "smoke test: {} was not detected as Chrome on Windows",
+            chrome
+        ));
+    }
+
+    let iphone = "Mozilla/5.0 (iPhone; CPU iPhone OS 17_0 like Mac OS X) AppleWebKit/605.1.15 (KHTML, like Gecko) Version/17.0 Mobile/15E148 Safari/604.1";
+    let detection = detector.parse(iphone, None)?;
+    let known = detection.get_known_device();
+    if known
+        .and_then(|x| x.device.as_ref())
+        .and_then(|x| x.brand.as_deref())
+        != Some("Apple")
+    {
+        return Err(anyhow!(
+            "smoke test: {} was not detected as an Apple device",
+            iphone
+        ));
+    }
+
+    for ua in corpus {
+        detector
+            .parse(ua, None)
+            .map_err(|err| anyhow!("smoke test: {} failed: {}", ua, err))?;
+    }
+
+    Ok(())
+}
+
+/// Something that changes whenever a file in `dir` is added, removed or
+/// modified.
+fn fingerprint(dir: &Path) -> Result<Vec<(String, u64, SystemTime)>> {
+    let mut files = Vec::new();
+    let mut dirs = vec![dir.to_path_buf()];
+
+    while let Some(dir) = dirs.pop() {
+        for entry in std::fs::read_dir(&dir)? {
+            let entry = entry?;
+            let metadata = entry.metadata()?;
+            if metadata.is_dir() {
+                dirs.push(entry.path());
+            } else {
+                files.push((
+                    entry.path().display().to_string(),
+                    metadata.len(),
+                    metadata.modified()?,
+                ));
+            }
+        }
+    }
+
+    files.sort();
+    Ok(files)
+}
+
+async fn watch(state: Arc<State>, dir: &Path, interval: Duration) {
+    eprintln!("Watching {} for changes", dir.display());
+
+    let mut last = fingerprint(dir).ok();
+    let mut ticks = tokio::time::interval(interval);
+
+    loop {
+        ticks.tick().await;
+
+        let current = match fingerprint(dir) {
+            Ok(current) => Some(current),
+            Err(err) => {
+                eprintln!("Unable to read {}: {}", dir.display(), err);
+                continue;
+            }
+        };
+
+        if current != last {
+            last = current;
+            if let Err(err) = state.reload().await {
+                eprintln!("Reload failed, keeping the current regexes: {:#}", err);
+            }
+        }
+    }
+}
+
+#[cfg(unix)]
+async fn reload_on_hangup(state: Arc<State>) {
+    use tokio::signal::unix::{signal, SignalKind};
+
+    let mut hangups = signal(SignalKind::hangup()).expect("failed to install SIGHUP handler");
+
+    while hangups.recv().await.is_some() {
+        if let Err(err) = state.reload().await {
+            eprintln!("Reload failed, keeping the current regexes: {:#}", err);
+        }
+    }
+}
+
+/// The json body `POST /detect` accepts, as an alternative to a bare user
+/// agent, so that client hints can be sent along with it.
+#[derive(Deserialize)]
+struct DetectRequest {
+    user_agent: String,
+    #[serde(default)]
+    headers: IndexMap<String, String>,
+}
+
+/// The client hints a browser will send once asked to with `Accept-CH`.
+const CLIENT_HINTS: &str = "Sec-CH-UA, Sec-CH-UA-Arch, Sec-CH-UA-Bitness, Sec-CH-UA-Full-Version, Sec-CH-UA-Full-Version-List, Sec-CH-UA-Mobile, Sec-CH-UA-Model, Sec-CH-UA-Platform, Sec-CH-UA-Platform-Version";
+
+/// The headers of a request that are of any use to the detector, besides
+/// the user agent.
+fn client_hint_headers(headers: &HeaderMap) -> Result<Vec<(String, String)>, Error> {
+    headers
+        .iter()
+        .filter(|(name, _)| {
+            name.as_str().starts_with("sec-ch-ua") || name.as_str() == "x-requested-with"
+        })
+        .map(|(name, value)| {
+            let value = std::str::from_utf8(value.as_bytes())?;
+            Ok((name.to_string(), value.to_owned()))
+        })
+        .collect()
+}
+
+fn is_json(headers: &HeaderMap) -> bool {
+    headers
+        .get(CONTENT_TYPE)
+        .and_then(|x| x.to_str().ok())
+        .and_then(|x| x.split(';').next())
+        .is_some_and(|x| x.trim().eq_ignore_ascii_case("application/json"))
+}
+
+/// The same shape as [`Error::to_value`], for requests that never got as far
+/// as detection.
+fn error_value(kind: &str, err: impl std::fmt::Display) -> serde_json::Value {
+    serde_json::json!({
+        "error": {
+            "kind": kind,
+            "message": err.to_string(),
+        }
+    })
+}
+
+fn invalid_request(err: impl std::fmt::Display) -> serde_json::Value {
+    error_value("invalid_request", err)
+}
+
+fn json_response(status: StatusCode, value: &serde_json::Value) -> Result<Response<Body>> {
+    Ok(Response::builder()
+        .status(status)
+        .header(CONTENT_TYPE, "application/json")
+        .body(full(serde_json::to_string(value)?))?)
+}
+
+fn bad_request(err: impl std::fmt::Display) -> Result<Response<Body>> {
+    json_response(StatusCode::BAD_REQUEST, &invalid_request(err))
+}
+
+fn unprocessable(err: Error) -> Result<Response<Body>> {
+    json_response(StatusCode::UNPROCESSABLE_ENTITY, &err.to_value())
+}
+
+/// Reads the whole body, as long as it is within `limit`.
+async fn read_body(req: Request<Incoming>, limit: usize) -> Result<Bytes> {
+    let declared = req
+        .headers()
+        .get(CONTENT_LENGTH)
+        .and_then(|x| x.to_str().ok())
+        .and_then(|x| x.parse::<usize>().ok());
+    if declared.is_some_and(|declared| declared > limit) {
+        return Err(PayloadTooLarge.into());
+    }
+
+    let mut body = req.into_body();
+    let mut bytes = Vec::new();
+    while let Some(chunk) = next_chunk(&mut body).await? {
+        if bytes.len() + chunk.len() > limit {
+            return Err(PayloadTooLarge.into());
+        }
+        bytes.extend_from_slice(&chunk);
+    }
+
+    Ok(bytes.into())
+}
+
+/// The next chunk of data from `body`, skipping over any trailers.
+async fn next_chunk(body: &mut Incoming) -> Result<Option<Bytes>> {
+    while let Some(frame) = body.frame().await {
+        if let Ok(chunk) = frame?.into_data() {
+            return Ok(Some(chunk));
+        }
+    }
+    Ok(None)
+}
+
+fn parse_flag(name: &str, value: &str) -> Result<bool, String> {
+    match value {
+        "true" | "1" => Ok(true),
+        "false" | "0" => Ok(false),
+        _ => Err(format!("{} should be true or false, not {}", name, value)),
+    }
+}
+
+/// The detector to use for `req`, which can ask for other
+/// [`DetectionOptions`](crate::options::DetectionOptions) in its query
+/// string, such as `?version_truncation=major` or `?skip_bot_detection=true`.
+fn request_detector(state: &State, req: &Request<Incoming>) -> Result<Arc<DeviceDetector>, String> {
+    let detector = state.detector();
+    let mut options = detector.options().clone();
+
+    let params = req.uri().query().unwrap_or_default().split('&');
+    for (name, value) in params.filter_map(|param| param.split_once('=')) {
+        // anything else, such as a cache buster, is none of our business
+        match name {
+            "version_truncation" => options.version_truncation = value.parse()?,
+            "skip_bot_detection" => options.skip_bot_detection = parse_flag(name, value)?,
+            "detect_bot_devices" => options.detect_bot_devices = parse_flag(name, value)?,
+            "discard_bot_information" => options.discard_bot_information = parse_flag(name, value)?,
+            _ => {}
+        }
+    }
+
+    if options == *detector.options() {
+        Ok(detector)
+    } else {
+        Ok(Arc::new(detector.with_options(options)))
+    }
+}
+
+async fn detect(
+    state: &State,
     detector: Arc<DeviceDetector>,
+    ua: String,
+    headers: Option<Vec<(String, String)>>,
+    permit: Permit,
 ) -> Result<Response<Body>> {
+    let detection = tokio::task::spawn_blocking(move || {
+        let _permit = permit;
+        detector.parse(&ua, headers)
+    })
+    .await
+    .map_err(|err| anyhow!("detection panicked: {}", err))?;
+
+    state.metrics.record_detection(&detection);
+
+    let detection = match detection {
+        Ok(detection) => detection,
+        Err(err) => return unprocessable(err),
+    };
+
+    let response = serde_json::to_string(&detection.to_value())?;
+
+    Ok(Response::new(full(response)))
+}
+
+/// How many user agents `/detect/batch` detects at a time, so results can be
+/// streamed back before the whole batch is done.
+const BATCH_CHUNK: usize = 1000;
+
+/// Each item of a batch is either a bare user agent or the same json object
+/// `POST /detect` accepts.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum BatchRequest {
+    UserAgent(String),
+    Detect(DetectRequest),
+}
+
+type BatchItem = Result<(String, Option<Vec<(String, String)>>), String>;
+
+fn batch_item(value: &serde_json::Value) -> BatchItem {
+    match BatchRequest::deserialize(value) {
+        Ok(BatchRequest::UserAgent(ua)) => Ok((ua, None)),
+        Ok(BatchRequest::Detect(request)) => Ok((
+            request.user_agent,
+            Some(request.headers.into_iter().collect()),
+        )),
+        Err(_) => {
+            Err("expected a user agent or {\"user_agent\": ..., \"headers\": {...}}".to_owned())
+        }
+    }
+}
+
+fn batch_threads() -> usize {
+    std::thread::available_parallelism()
+        .map(|threads| threads.get())
+        .unwrap_or(1)
+}
+
+/// Detects a chunk of a batch, one line of json per item, in order. Items
+/// that failed, or weren't valid in the first place, get an `error`.
+async fn detect_chunk(
+    state: &State,
+    detector: &Arc<DeviceDetector>,
+    items: Vec<BatchItem>,
+    permit: &Permit,
+) -> Result<Bytes> {
+    let detector = detector.clone();
+    let metrics = state.metrics.clone();
+    let permit = permit.clone();
+
+    tokio::task::spawn_blocking(move || {
+        let _permit = permit;
+        let mut requests = Vec::new();
+        let mut errors = Vec::new();
+        for item in items {
+            match item {
+                Ok(request) => {
+                    requests.push(request);
+                    errors.push(None);
+                }
+                Err(err) => errors.push(Some(err)),
+            }
+        }
+
+        let mut detections = detector.parse_batch(&requests, batch_threads()).into_iter();
+
+        let mut lines = String::new();
+        for error in errors {
+            let value = match error {
+                Some(err) => invalid_request(err),
+                None => {
+                    let detection = detections.next().expect("a detection for every request");
+                    metrics.record_detection(&detection);
+                    match detection {
+                        Ok(detection) => detection.to_value(),
+                        Err(err) => err.to_value(),
+                    }
+                }
+            };
+            lines.push_str(&serde_json::to_string(&value)?);
+            lines.push('\n');
+        }
+
+        Ok(Bytes::from(lines))
+    })
+    .await
+    .map_err(|err| anyhow!("batch detection panicked: {}", err))?
+}
+
+/// Reads newline delimited json from `body` and writes the results to
+/// `sender` as it goes, so neither has to be held in memory in full.
+async fn stream_batch(
+    state: Arc<State>,
+    detector: Arc<DeviceDetector>,
+    mut body: Incoming,
+    sender: &mut Sender<Bytes, anyhow::Error>,
+    permit: Permit,
+) -> Result<()> {
+    let mut buffer = Vec::new();
+    let mut items = Vec::new();
+    let mut done = false;
+
+    while !done {
+        match next_chunk(&mut body).await? {
+            Some(chunk) => {
+                buffer.extend_from_slice(&chunk);
+                // a line can only be so long, whether or not it has ended
+                let line = buffer
+                    .iter()
+                    .rposition(|x| *x == b'\n')
+                    .map_or(0, |x| x + 1);
+                if buffer.len() - line > state.limits.max_body_bytes {
+                    return Err(PayloadTooLarge.into());
+                }
+            }
+            None => {
+                // the last line needn't end with a newline
+                buffer.push(b'\n');
+                done = true;
+            }
+        }
+
+        while let Some(end) = buffer.iter().position(|x| *x == b'\n') {
+            let line: Vec<u8> = buffer.drain(..=end).collect();
+            if line.iter().all(u8::is_ascii_whitespace) {
+                continue;
+            }
+            let item = match serde_json::from_slice(&line) {
+                Ok(value) => batch_item(&value),
+                Err(err) => Err(err.to_string()),
+            };
+            items.push(item);
+        }
+
+        if items.len() >= BATCH_CHUNK || (done && !items.is_empty()) {
+            let lines =
+                detect_chunk(&state, &detector, std::mem::take(&mut items), &permit).await?;
+            sender.send_data(lines).await?;
+        }
+    }
+
+    Ok(())
+}
+
+/// `POST /detect/batch`. Takes either a json array or newline delimited
+/// json, each item a user agent or a `/detect` json object, and responds
+/// with one line of json per item, in the same order.
+async fn detect_batch(
+    req: Request<Incoming>,
+    state: Arc<State>,
+    detector: Arc<DeviceDetector>,
+    permit: Permit,
+) -> Result<Response<Body>> {
+    let json = is_json(req.headers());
+    let (mut sender, response) = Channel::new(1);
+
+    if json {
+        let body = read_body(req, state.limits.max_body_bytes).await?;
+        let requests: Vec<serde_json::Value> = match serde_json::from_slice(&body) {
+            Ok(requests) => requests,
+            Err(err) => return bad_request(err),
+        };
+
+        tokio::spawn(async move {
+            for chunk in requests.chunks(BATCH_CHUNK) {
+                let items = chunk.iter().map(batch_item).collect();
+
+                let lines = match detect_chunk(&state, &detector, items, &permit).await {
+                    Ok(lines) => lines,
+                    Err(err) => {
+                        eprintln!("Batch detection failed: {:#}", err);
+                        sender.abort(err);
+                        return;
+                    }
+                };
+
+                if sender.send_data(lines).await.is_err() {
+                    // the client went away
+                    return;
+                }
+            }
+        });
+    } else {
+        let body = req.into_body();
+        tokio::spawn(async move {
+            if let Err(err) = stream_batch(state, detector, body, &mut sender, permit).await {
+                eprintln!("Batch detection failed: {:#}", err);
+                sender.abort(err);
+            }
+        });
+    }
+
+    Ok(Response::builder()
+        .header(CONTENT_TYPE, "application/x-ndjson")
+        .body(response.boxed())?)
+}
+
+async fn handle(req: Request<Incoming>, state: Arc<State>) -> Result<Response<Body>> {
+    let started = Instant::now();
+    let route = metrics::route(req.uri().path());
+
+    let response = limit(req, state.clone()).await;
+
+    let status = match &response {
+        Ok(response) => response.status(),
+        Err(_) => StatusCode::INTERNAL_SERVER_ERROR,
+    };
+    state
+        .metrics
+        .record_request(route, status, started.elapsed());
+
+    response
+}
+
+/// Applies the [`Limits`] to every request.
+async fn limit(req: Request<Incoming>, state: Arc<State>) -> Result<Response<Body>> {
+    // health checks, metrics and reloads shouldn't fail just because the
+    // server is busy, and a reload takes as long as it takes.
+    match (req.method(), req.uri().path()) {
+        (&Method::GET, "/health") => return Ok(Response::new(full("OK\n"))),
+        (&Method::GET, "/metrics") => {
+            let metrics = state.metrics.render(&state.detector());
+            return Ok(Response::builder()
+                .header(CONTENT_TYPE, "text/plain; version=0.0.4")
+                .body(full(metrics))?);
+        }
+        (&Method::POST, "/reload") => {
+            if let Some(token) = state
+                .reload
+                .as_ref()
+                .and_then(|reload| reload.token.as_ref())
+            {
+                return reload(&req, token, &state).await;
+            }
+        }
+        _ => (),
+    }
+
+    let Ok(permit) = state.requests.clone().try_acquire_owned() else {
+        return json_response(
+            StatusCode::SERVICE_UNAVAILABLE,
+            &error_value("too_many_requests", "too many requests at once"),
+        );
+    };
+
+    let timeout = state.limits.request_timeout;
+    let response = tokio::time::timeout(timeout, serve_request(req, state, Arc::new(permit)));
+
+    match response.await {
+        Ok(Err(err)) if err.is::<PayloadTooLarge>() => json_response(
+            StatusCode::PAYLOAD_TOO_LARGE,
+            &error_value("payload_too_large", err),
+        ),
+        Ok(response) => response,
+        Err(_elapsed) => json_response(
+            StatusCode::REQUEST_TIMEOUT,
+            &error_value("timeout", format!("request took longer than {:?}", timeout)),
+        ),
+    }
+}
+
+/// `POST /reload`, for those who know the token.
+async fn reload(
+    req: &Request<Incoming>,
+    token: &str,
+    state: &Arc<State>,
+) -> Result<Response<Body>> {
+    let given = req
+        .headers()
+        .get(AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "));
+
+    if !given.is_some_and(|given| same_token(given, token)) {
+        return Ok(Response::builder()
+            .status(StatusCode::UNAUTHORIZED)
+            .header(WWW_AUTHENTICATE, "Bearer")
+            .body(full("missing or wrong reload token\n"))?);
+    }
+
+    match state.reload().await {
+        Ok(()) => Ok(Response::new(full("OK\n"))),
+        Err(err) => {
+            eprintln!("Reload failed, keeping the current regexes: {:#}", err);
+            let status = if err.is::<ReloadInProgress>() {
+                StatusCode::CONFLICT
+            } else {
+                StatusCode::INTERNAL_SERVER_ERROR
+            };
+            Ok(Response::builder()
+                .status(status)
+                .body(full(format!("{:#}\n", err)))?)
+        }
+    }
+}
+
+/// Compares every byte however early they differ, so how long it takes
+/// gives nothing away about the token.
+fn same_token(given: &str, token: &str) -> bool {
+    given.len() == token.len()
+        && given
+            .bytes()
+            .zip(token.bytes())
+            .fold(0, |diff, (a, b)| diff | (a ^ b))
+            == 0
+}
+
+async fn serve_request(
+    req: Request<Incoming>,
+    state: Arc<State>,
+    permit: Permit,
+) -> Result<Response<Body>> {
+    let detector = match req.uri().path() {
+        "/detect" | "/detect/batch" => match request_detector(&state, &req) {
+            Ok(detector) => detector,
+            Err(err) => return bad_request(err),
+        },
+        _ => state.detector(),
+    };
+
     match (req.method(), req.uri().path()) {
         (&Method::POST, "/detect") => {
-            // TODO prevent pulling entire body into memory in case of abuse
-            let body = hyper::body::to_bytes(req.into_body()).await?;
-            let body = String::from_utf8(body.to_vec())?;
+            let json = is_json(req.headers());
 
-            let detection = detector.parse(&body, None);
+            let body = read_body(req, state.limits.max_body_bytes).await?;
 
-            let detection = detection.unwrap_or_else(|err| {
-                panic!("error: {:?} ua: {}", &err, &body);
-            });
+            if json {
+                let request: DetectRequest = match serde_json::from_slice(&body) {
+                    Ok(request) => request,
+                    Err(err) => return bad_request(err),
+                };
+
+                let headers = request.headers.into_iter().collect();
+                return detect(&state, detector, request.user_agent, Some(headers), permit).await;
+            }
+
+            let body = match String::from_utf8(body.to_vec()) {
+                Ok(body) => body,
+                Err(err) => return unprocessable(err.into()),
+            };
+            detect(&state, detector, body, None, permit).await
+        }
+
+        (&Method::POST, "/detect/batch") => detect_batch(req, state, detector, permit).await,
 
-            let response = serde_json::to_string(&detection.to_value())?;
+        // the client hints of the request itself, for when browsers are sent
+        // here directly.
+        (&Method::GET, "/detect") => {
+            let Some(ua) = req.headers().get(USER_AGENT) else {
+                return bad_request("no User-Agent header");
+            };
+            let Ok(ua) = std::str::from_utf8(ua.as_bytes()) else {
+                return unprocessable(Error::InvalidUtf8);
+            };
 
-            Ok(Response::new(Body::from(response)))
+            let headers = match client_hint_headers(req.headers()) {
+                Ok(headers) => headers,
+                Err(err) => return unprocessable(err),
+            };
+
+            let ua = ua.to_owned();
+            let mut response = detect(&state, detector, ua, Some(headers), permit).await?;
+
+            // ask for the rest of the client hints from now on
+            response
+                .headers_mut()
+                .insert("accept-ch", CLIENT_HINTS.parse()?);
+
+            Ok(response)
         }
 
-        (&Method::GET, "/health") => Ok(Response::new("OK\n".into())),
+        (&Method::GET, "/diagnostics") => {
+            let diagnostics = state.detector().diagnostics().clone();
+            let response = serde_json::json!({
+                "regex_failures": diagnostics.regex_failures(),
+            });
+
+            Ok(Response::new(full(serde_json::to_string(&response)?)))
+        }
 
         _route => {
-            let err = "valid routes:\n  POST /detect with a body containing referer\n  GET  /health for heartbeat";
+            let err = "valid routes:\n  POST /detect with a body containing a user agent, or json {\"user_agent\": ..., \"headers\": {...}}\n  GET  /detect to detect the request's own User-Agent and client hint headers\n  POST /detect/batch with a json array or newline delimited json of the above, one json line out per item\n  GET  /health for heartbeat\n  GET  /metrics for prometheus\n  GET  /diagnostics for regexes that hit the backtrack limit\n  POST /reload to reload regexes (if enabled, with the reload token)";
             eprintln!("{}", err);
             Ok(Response::builder()
                 .status(StatusCode::NOT_FOUND)
-                .body(Body::from(err))?)
+                .body(full(err))?)
         }
     }
 }
 
+/// Resolves on SIGTERM or ctrl-c, whichever comes first.
 async fn shutdown_signal() {
-    tokio::signal::ctrl_c()
-        .await
-        .expect("failed to install CTRL+C signal handler");
+    let ctrl_c = async {
+        tokio::signal::ctrl_c()
+            .await
+            .expect("failed to install CTRL+C signal handler");
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        use tokio::signal::unix::{signal, SignalKind};
+
+        signal(SignalKind::terminate())
+            .expect("failed to install SIGTERM handler")
+            .recv()
+            .await;
+    };
+
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => (),
+        _ = terminate => (),
+    }
 }
 
-pub async fn server(listen_address: SocketAddr, device_detector: DeviceDetector) {
-    eprintln!("Listening on {}", listen_address);
+/// A connection from either kind of listener.
+trait Io: AsyncRead + AsyncWrite + Send + Unpin {}
 
-    let device_detector = Arc::new(device_detector);
+impl<T: AsyncRead + AsyncWrite + Send + Unpin> Io for T {}
 
-    let make_svc = make_service_fn(|_conn| {
-        let device_detector = device_detector.clone();
+enum Listener {
+    Tcp(TcpListener),
+    #[cfg(unix)]
+    Unix(tokio::net::UnixListener, PathBuf),
+}
 
-        let service = service_fn(move |req| {
-            let device_detector = device_detector.clone();
-            serve_request(req, device_detector)
-        });
+impl Listener {
+    async fn bind(listen: &Listen) -> std::io::Result<Self> {
+        match listen {
+            Listen::Tcp(addr) => Ok(Self::Tcp(TcpListener::bind(addr).await?)),
+            #[cfg(unix)]
+            Listen::Unix(path) => {
+                use std::os::unix::fs::FileTypeExt;
 
-        async move { Ok::<_, Infallible>(service) }
-    });
+                // a socket nobody is listening on any more is left over from
+                // a previous run, and would stop us binding.
+                let stale = std::fs::symlink_metadata(path)
+                    .is_ok_and(|metadata| metadata.file_type().is_socket())
+                    && std::os::unix::net::UnixStream::connect(path).is_err();
+                if stale {
+                    std::fs::remove_file(path)?;
+                }
 
-    let server = Server::bind(&listen_address).serve(make_svc);
+                let listener = tokio::net::UnixListener::bind(path)?;
+                Ok(Self::Unix(listener, path.clone()))
+            }
+        }
+    }
 
-    let server = server.with_graceful_shutdown(shutdown_signal());
+    async fn accept(&self) -> std::io::Result<Box<dyn Io>> {
+        match self {
+            Self::Tcp(listener) => {
+                let (stream, _) = listener.accept().await?;
+                stream.set_nodelay(true)?;
+                Ok(Box::new(stream))
+            }
+            #[cfg(unix)]
+            Self::Unix(listener, _) => {
+                let (stream, _) = listener.accept().await?;
+                Ok(Box::new(stream))
+            }
+        }
+    }
 
-    // Run this server for... forever!
-    if let Err(e) = server.await {
-        eprintln!("server error: {}", e);
+    fn close(self) {
+        #[cfg(unix)]
+        if let Self::Unix(listener, path) = self {
+            drop(listener);
+            if let Err(err) = std::fs::remove_file(&path) {
+                eprintln!("Unable to remove {}: {}", path.display(), err);
+            }
+        }
+    }
+}
+
+pub async fn server(listen: impl Into<Listen>, device_detector: DeviceDetector) {
+    server_with_options(listen, device_detector, Options::default()).await
+}
+
+/// Like [`server`], but the detector can be replaced while running without
+/// dropping any requests.
+pub async fn server_with_reload(
+    listen: impl Into<Listen>,
+    device_detector: DeviceDetector,
+    reload: Reload,
+) {
+    let options = Options {
+        reload: Some(reload),
+        ..Options::default()
     };
+    server_with_options(listen, device_detector, options).await
+}
+
+/// Like [`server`], with everything configurable. Runs until SIGTERM or
+/// ctrl-c, and then until the requests in flight have finished or
+/// [`Options::shutdown_timeout`] has passed.
+pub async fn server_with_options(
+    listen: impl Into<Listen>,
+    device_detector: DeviceDetector,
+    options: Options,
+) {
+    let listen = listen.into();
+
+    let listener = Listener::bind(&listen)
+        .await
+        .unwrap_or_else(|err| panic!("error binding to {}: {}", listen, err));
+
+    eprintln!("Listening on {}", listen);
+
+    let state = Arc::new(State {
+        detector: RwLock::new(Arc::new(device_detector)),
+        reload: options.reload,
+        reloading: tokio::sync::Mutex::new(()),
+        requests: Arc::new(Semaphore::new(options.limits.max_concurrent_requests)),
+        metrics: Default::default(),
+        limits: options.limits,
+    });
+
+    if let Some(reload) = &state.reload {
+        #[cfg(unix)]
+        tokio::spawn(reload_on_hangup(state.clone()));
+
+        if let Some(interval) = reload.watch_interval {
+            match reload.builder.get_regex_dir() {
+                Some(dir) => {
+                    let dir = dir.to_path_buf();
+                    let state = state.clone();
+                    tokio::spawn(async move { watch(state, &dir, interval).await });
+                }
+                None => eprintln!("Not watching for changes, the regexes are compiled in"),
+            }
+        }
+    }
+
+    let mut builder = auto::Builder::new(TokioExecutor::new());
+    builder
+        .http1()
+        .keep_alive(options.keep_alive)
+        .timer(TokioTimer::new())
+        .header_read_timeout(state.limits.header_read_timeout);
+
+    let graceful = GracefulShutdown::new();
+    let mut shutdown = std::pin::pin!(shutdown_signal());
+
+    loop {
+        let io = tokio::select! {
+            io = listener.accept() => io,
+            _ = &mut shutdown => break,
+        };
+
+        let io = match io {
+            Ok(io) => io,
+            Err(err) => {
+                // most likely out of file descriptors, which waiting may fix
+                eprintln!("Unable to accept connection: {}", err);
+                tokio::time::sleep(Duration::from_millis(100)).await;
+                continue;
+            }
+        };
+
+        let state = state.clone();
+        let service = service_fn(move |req| handle(req, state.clone()));
+
+        let connection = builder.serve_connection(TokioIo::new(io), service);
+        let connection = graceful.watch(connection.into_owned());
+
+        // errors here are clients going away or timing out, which is their
+        // problem rather than ours.
+        tokio::spawn(connection);
+    }
+
+    listener.close();
+
+    let timeout = options.shutdown_timeout;
+    eprintln!(
+        "Shutting down, waiting up to {:?} for requests in flight",
+        timeout
+    );
+    if tokio::time::timeout(timeout, graceful.shutdown())
+        .await
+        .is_err()
+    {
+        eprintln!("Requests still in flight after {:?}, giving up", timeout);
+    }
 }
@@ -0,0 +1,243 @@
+use hyper::http::StatusCode;
+
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use crate::device_detector::{Detection, DeviceDetector};
+use crate::error::Error;
+
+/// Upper bounds of the request latency histogram buckets, in seconds.
+const LATENCY_BUCKETS: [f64; 14] = [
+    0.0005, 0.001, 0.0025, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0,
+];
+
+/// Every route, so that requests for anything else don't each get their own
+/// series.
+const ROUTES: [&str; 6] = [
+    "/detect",
+    "/detect/batch",
+    "/health",
+    "/metrics",
+    "/diagnostics",
+    "/reload",
+];
+
+#[derive(Default)]
+struct Histogram {
+    buckets: [u64; LATENCY_BUCKETS.len()],
+    sum: f64,
+    count: u64,
+}
+
+/// What the server has done so far, for `GET /metrics`.
+#[derive(Default)]
+pub(super) struct Metrics {
+    inner: Mutex<Inner>,
+}
+
+#[derive(Default)]
+struct Inner {
+    requests: BTreeMap<(&'static str, u16), u64>,
+    latency: BTreeMap<&'static str, Histogram>,
+    detections: BTreeMap<&'static str, u64>,
+    device_types: BTreeMap<&'static str, u64>,
+    client_types: BTreeMap<&'static str, u64>,
+    errors: BTreeMap<&'static str, u64>,
+}
+
+/// The route label for `path`.
+pub(super) fn route(path: &str) -> &'static str {
+    ROUTES
+        .iter()
+        .find(|route| **route == path)
+        .copied()
+        .unwrap_or("other")
+}
+
+impl Metrics {
+    pub(super) fn record_request(&self, route: &'static str, status: StatusCode, took: Duration) {
+        let mut inner = self.inner.lock().unwrap();
+
+        *inner.requests.entry((route, status.as_u16())).or_default() += 1;
+
+        let histogram = inner.latency.entry(route).or_default();
+        let took = took.as_secs_f64();
+        for (bucket, le) in histogram.buckets.iter_mut().zip(LATENCY_BUCKETS) {
+            if took <= le {
+                *bucket += 1;
+            }
+        }
+        histogram.sum += took;
+        histogram.count += 1;
+    }
+
+    pub(super) fn record_detection(&self, detection: &Result<Detection, Error>) {
+        let mut inner = self.inner.lock().unwrap();
+
+        let known = match detection {
+            Ok(Detection::Bot(_) | Detection::AnonymousBot) => {
+                *inner.detections.entry("bot").or_default() += 1;
+                return;
+            }
+            Ok(Detection::Known(known)) => known,
+            Err(err) => {
+                *inner.errors.entry(err.kind()).or_default() += 1;
+                return;
+            }
+        };
+
+        // bots found with detect_bot_devices still count as bots, but their
+        // devices and clients are counted as well
+        let kind = if known.is_bot() { "bot" } else { "known" };
+        *inner.detections.entry(kind).or_default() += 1;
+
+        let device_type = known
+            .device
+            .as_ref()
+            .and_then(|x| x.device_type.as_ref())
+            .map_or("unknown", |x| x.as_str());
+        *inner.device_types.entry(device_type).or_default() += 1;
+
+        let client_type = known
+            .client
+            .as_ref()
+            .map_or("unknown", |x| x.r#type.as_str());
+        *inner.client_types.entry(client_type).or_default() += 1;
+    }
+
+    /// Everything so far, in the prometheus text format.
+    pub(super) fn render(&self, detector: &DeviceDetector) -> String {
+        let mut out = String::new();
+        self.render_into(&mut out, detector)
+            .expect("writing to a string");
+        out
+    }
+
+    fn render_into(&self, out: &mut String, detector: &DeviceDetector) -> std::fmt::Result {
+        let inner = self.inner.lock().unwrap();
+
+        describe(
+            out,
+            "rdd_http_requests_total",
+            "counter",
+            "Requests handled, by route and status.",
+        )?;
+        for ((route, status), count) in inner.requests.iter() {
+            writeln!(
+                out,
+                "rdd_http_requests_total{{route=\"{}\",status=\"{}\"}} {}",
+                route, status, count
+            )?;
+        }
+
+        let name = "rdd_http_request_duration_seconds";
+        describe(out, name, "histogram", "How long requests took, by route.")?;
+        for (route, histogram) in inner.latency.iter() {
+            for (le, count) in LATENCY_BUCKETS.iter().zip(histogram.buckets) {
+                writeln!(
+                    out,
+                    "{}_bucket{{route=\"{}\",le=\"{}\"}} {}",
+                    name, route, le, count
+                )?;
+            }
+            writeln!(
+                out,
+                "{}_bucket{{route=\"{}\",le=\"+Inf\"}} {}",
+                name, route, histogram.count
+            )?;
+            writeln!(out, "{}_sum{{route=\"{}\"}} {}", name, route, histogram.sum)?;
+            writeln!(
+                out,
+                "{}_count{{route=\"{}\"}} {}",
+                name, route, histogram.count
+            )?;
+        }
+
+        counters(
+            out,
+            "rdd_detections_total",
+            "Detections, by whether they were a bot or a known device.",
+            "kind",
+            &inner.detections,
+        )?;
+        counters(
+            out,
+            "rdd_detections_by_device_type_total",
+            "Known device detections, by device type.",
+            "device_type",
+            &inner.device_types,
+        )?;
+        counters(
+            out,
+            "rdd_detections_by_client_type_total",
+            "Known device detections, by client type.",
+            "client_type",
+            &inner.client_types,
+        )?;
+        counters(
+            out,
+            "rdd_detection_errors_total",
+            "Detections that failed, by kind of error.",
+            "kind",
+            &inner.errors,
+        )?;
+
+        // these belong to the detector, and so start over when the regexes
+        // are reloaded, which prometheus copes with like any restart.
+        let cache = detector.cache_stats();
+        describe(
+            out,
+            "rdd_cache_hits_total",
+            "counter",
+            "Detections answered from the cache.",
+        )?;
+        writeln!(out, "rdd_cache_hits_total {}", cache.hits)?;
+        describe(
+            out,
+            "rdd_cache_misses_total",
+            "counter",
+            "Detections not in the cache.",
+        )?;
+        writeln!(out, "rdd_cache_misses_total {}", cache.misses)?;
+        describe(
+            out,
+            "rdd_cache_entries",
+            "gauge",
+            "Detections in the cache.",
+        )?;
+        writeln!(out, "rdd_cache_entries {}", cache.entries)?;
+
+        let mut failures = BTreeMap::new();
+        for failure in detector.diagnostics().regex_failures() {
+            *failures.entry(failure.list).or_default() += failure.count;
+        }
+        counters(
+            out,
+            "rdd_regex_failures_total",
+            "Regexes that hit the backtrack limit, by the file they are from.",
+            "list",
+            &failures,
+        )
+    }
+}
+
+fn describe(out: &mut String, name: &str, kind: &str, help: &str) -> std::fmt::Result {
+    writeln!(out, "# HELP {} {}", name, help)?;
+    writeln!(out, "# TYPE {} {}", name, kind)
+}
+
+fn counters(
+    out: &mut String,
+    name: &str,
+    help: &str,
+    label: &str,
+    counts: &BTreeMap<&'static str, u64>,
+) -> std::fmt::Result {
+    describe(out, name, "counter", help)?;
+    for (value, count) in counts.iter() {
+        writeln!(out, "{}{{{}=\"{}\"}} {}", name, label, value, count)?;
+    }
+    Ok(())
+}
@@ -0,0 +1,1974 @@
+use std::collections::HashMap;
+
+mod codes;
+
+// These are the brands devices are detected as, every one named in
+// regexes/device/*.yml and regexes/vendorfragments.yml. As with the known
+// browsers and operating systems, this has to be kept up to date by hand when
+// the regexes are updated, which tests/php_tests/parser/device/brands.rs
+// checks.
+//
+// Their short codes are upstream's, from the `$deviceBrands` table in
+// Parser/Device/AbstractDeviceParser.php, which lives in php rather than
+// yaml. copy_static_files.sh writes it out to known_brands/codes.rs.
+
+pub struct AvailableBrands {
+    brands_by_short: HashMap<String, AvailableBrand>,
+    brands_by_name: HashMap<String, AvailableBrand>,
+}
+
+#[derive(Clone, Debug)]
+pub struct AvailableBrand {
+    /// Matomo's code for this brand, such as `AP` for Apple, if upstream's
+    /// table has one for it.
+    pub short_name: Option<String>,
+    pub name: String,
+}
+
+impl Default for AvailableBrands {
+    fn default() -> Self {
+        let brands_by_short = codes::brand_codes()
+            .into_iter()
+            .map(|(short, name)| {
+                (
+                    short.to_owned(),
+                    AvailableBrand {
+                        short_name: Some(short.to_owned()),
+                        name: name.to_owned(),
+                    },
+                )
+            })
+            .collect::<HashMap<String, AvailableBrand>>();
+
+        let mut brands_by_name = available_brands()
+            .into_iter()
+            .map(|name| {
+                (
+                    name.replace(' ', "").to_lowercase(),
+                    AvailableBrand {
+                        short_name: None,
+                        name: name.to_owned(),
+                    },
+                )
+            })
+            .collect::<HashMap<String, AvailableBrand>>();
+        brands_by_name.extend(
+            brands_by_short
+                .values()
+                .map(|brand| (brand.name.replace(' ', "").to_lowercase(), brand.clone())),
+        );
+
+        Self {
+            brands_by_short,
+            brands_by_name,
+        }
+    }
+}
+
+impl AvailableBrands {
+    /// Finds a brand whatever its case or spacing, so `sony ericsson` gives
+    /// `Sony Ericsson`.
+    pub fn search_by_name(&self, name: &str) -> Option<&AvailableBrand> {
+        let name = name.to_lowercase().replace(' ', "");
+        self.brands_by_name.get(&name)
+    }
+
+    /// Looks up a brand by its short code, such as `AP`.
+    pub fn search_by_short(&self, short: &str) -> Option<&AvailableBrand> {
+        self.brands_by_short.get(short)
+    }
+
+    /// Every known brand, in no particular order.
+    pub fn iter(&self) -> impl Iterator<Item = &AvailableBrand> {
+        self.brands_by_name.values()
+    }
+
+    pub fn len(&self) -> usize {
+        self.brands_by_name.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.brands_by_name.is_empty()
+    }
+}
+
+fn available_brands() -> Vec<&'static str> {
+    vec![
+        "10moons",
+        "2E",
+        "360",
+        "3GNET",
+        "3GO",
+        "3Q",
+        "4Good",
+        "4ife",
+        "5IVE",
+        "7 Mobile",
+        "8848",
+        "A&K",
+        "A1",
+        "A95X",
+        "AAUW",
+        "Accent",
+        "Accesstyle",
+        "Ace",
+        "Aceline",
+        "Acer",
+        "Acteck",
+        "actiMirror",
+        "Adronix",
+        "Advan",
+        "Advance",
+        "Advantage Air",
+        "AEEZO",
+        "AFFIX",
+        "AfriOne",
+        "AG Mobile",
+        "AGM",
+        "AIDATA",
+        "Ainol",
+        "Airis",
+        "Airness",
+        "AIRON",
+        "Airpha",
+        "Airtel",
+        "Airties",
+        "AirTouch",
+        "AIS",
+        "Aiuto",
+        "Aiwa",
+        "Ajib",
+        "Akai",
+        "AKIRA",
+        "Alba",
+        "Alcatel",
+        "Alcor",
+        "ALDI NORD",
+        "ALDI SÜD",
+        "Alfawise",
+        "Alienware",
+        "Aligator",
+        "AllCall",
+        "AllDocube",
+        "ALLINmobile",
+        "Allview",
+        "Allwinner",
+        "Alps",
+        "Altech UEC",
+        "Altice",
+        "Altimo",
+        "altron",
+        "Altus",
+        "AMA",
+        "Amazon",
+        "Amazon Basics",
+        "AMCV",
+        "AMGOO",
+        "Amigoo",
+        "Amino",
+        "Amoi",
+        "ANBERNIC",
+        "Andowl",
+        "Angelcare",
+        "AngelTech",
+        "Anker",
+        "Anry",
+        "ANS",
+        "ANXONIT",
+        "AOC",
+        "Aocos",
+        "Aocwei",
+        "AOpen",
+        "Aoro",
+        "Aoson",
+        "AOYODKG",
+        "Apple",
+        "Aquarius",
+        "Archos",
+        "Arian Space",
+        "Arival",
+        "Ark",
+        "ArmPhone",
+        "Arnova",
+        "ARRIS",
+        "Artel",
+        "Artizlee",
+        "ArtLine",
+        "Arçelik",
+        "Asano",
+        "Asanzo",
+        "Ask",
+        "Aspera",
+        "ASSE",
+        "Assistant",
+        "Astro",
+        "Asus",
+        "AT&T",
+        "Athesi",
+        "Atlantic Electrics",
+        "Atmaca Elektronik",
+        "ATMAN",
+        "ATOL",
+        "Atom",
+        "Atozee",
+        "Attila",
+        "Atvio",
+        "Audiovox",
+        "AUPO",
+        "AURIS",
+        "Autan",
+        "AUX",
+        "Avaya",
+        "Avenzo",
+        "AVH",
+        "Avvio",
+        "Awow",
+        "AWOX",
+        "AXEN",
+        "Axioo",
+        "AXXA",
+        "Axxion",
+        "AYA",
+        "AYYA",
+        "Azeyou",
+        "Azumi Mobile",
+        "b2m",
+        "Backcell",
+        "BAFF",
+        "BangOlufsen",
+        "Barnes & Noble",
+        "BARTEC",
+        "BASE",
+        "BAUHN",
+        "BB Mobile",
+        "BBK",
+        "BDF",
+        "BDQ",
+        "BDsharing",
+        "Beafon",
+        "Becker",
+        "Beeline",
+        "Beelink",
+        "Beetel",
+        "Beista",
+        "Beko",
+        "Bellphone",
+        "Benco",
+        "Benesse",
+        "BenQ",
+        "BenQ-Siemens",
+        "BenWee",
+        "Benzo",
+        "Beyond",
+        "Bezkam",
+        "BGH",
+        "Bigben",
+        "BIHEE",
+        "BilimLand",
+        "Billion",
+        "Billow",
+        "BioRugged",
+        "Bird",
+        "Bitel",
+        "Bitmore",
+        "Bittium",
+        "Bkav",
+        "Black Bear",
+        "Black Box",
+        "Black Fox",
+        "Blackpcs",
+        "Blackview",
+        "Blaupunkt",
+        "Bleck",
+        "BLISS",
+        "Blloc",
+        "Blow",
+        "Blu",
+        "Bluboo",
+        "Bluebird",
+        "Bluedot",
+        "Bluegood",
+        "BlueSky",
+        "Bluewave",
+        "BluSlate",
+        "BMAX",
+        "Bmobile",
+        "BMW",
+        "BMXC",
+        "Bobarry",
+        "bogo",
+        "Bolva",
+        "Bookeen",
+        "Boost",
+        "Botech",
+        "Boway",
+        "bq",
+        "BrandCode",
+        "Brandt",
+        "BRAVE",
+        "Bravis",
+        "BrightSign",
+        "Brigmton",
+        "Brondi",
+        "BROR",
+        "BS Mobile",
+        "Bubblegum",
+        "Bundy",
+        "Bush",
+        "BuzzTV",
+        "BYYBUO",
+        "C Idea",
+        "C5 Mobile",
+        "CADENA",
+        "CAGI",
+        "Camfone",
+        "Canal Digital",
+        "Canal+",
+        "Canguro",
+        "Capitel",
+        "Captiva",
+        "Carbon Mobile",
+        "Carrefour",
+        "Casio",
+        "Casper",
+        "Cat",
+        "Cavion",
+        "CCIT",
+        "Cecotec",
+        "Ceibal",
+        "Celcus",
+        "Celkon",
+        "Cell-C",
+        "Cellacom",
+        "CellAllure",
+        "Cellution",
+        "CENTEK",
+        "Centric",
+        "CEPTER",
+        "CG Mobile",
+        "CGV",
+        "Chainway",
+        "Changhong",
+        "Cherry Mobile",
+        "Chico Mobile",
+        "ChiliGreen",
+        "China Mobile",
+        "China Telecom",
+        "Chuwi",
+        "CipherLab",
+        "Citycall",
+        "CKK Mobile",
+        "Claresta",
+        "Clarmin",
+        "CLAYTON",
+        "ClearPHONE",
+        "Clementoni",
+        "Cloud",
+        "Cloudfone",
+        "Cloudpad",
+        "Clout",
+        "Clovertek",
+        "CnM",
+        "Cobalt",
+        "Coby Kyros",
+        "Colors",
+        "Comio",
+        "Compal",
+        "Compaq",
+        "COMPUMAX",
+        "ComTrade Tesla",
+        "Conceptum",
+        "Concord",
+        "ConCorde",
+        "Condor",
+        "Connectce",
+        "Connex",
+        "Conquest",
+        "Continental Edison",
+        "Contixo",
+        "COOD-E",
+        "Coolpad",
+        "Coopers",
+        "CORN",
+        "Cosmote",
+        "Covia",
+        "Cowon",
+        "COYOTE",
+        "CreNova",
+        "Crescent",
+        "Cricket",
+        "Crius Mea",
+        "Crony",
+        "Crosscall",
+        "Crown",
+        "Ctroniq",
+        "Cube",
+        "CUBOT",
+        "Cuiud",
+        "CVTE",
+        "Cwowdefu",
+        "Cyrus",
+        "D-Link",
+        "D-Tech",
+        "Daewoo",
+        "Danew",
+        "DangcapHD",
+        "Dany",
+        "Daria",
+        "DASS",
+        "Datalogic",
+        "Datamini",
+        "Datang",
+        "Datawind",
+        "Datsun",
+        "Dazen",
+        "DbPhone",
+        "Dbtel",
+        "Dcode",
+        "DEALDIG",
+        "Dell",
+        "Denali",
+        "Denver",
+        "Desay",
+        "DeWalt",
+        "DEXP",
+        "DEYI",
+        "DF",
+        "DGTEC",
+        "DIALN",
+        "Dialog",
+        "Dicam",
+        "Digi",
+        "Digicel",
+        "DIGICOM",
+        "Digidragon",
+        "DIGIFORS",
+        "Digihome",
+        "Digiland",
+        "Digit4G",
+        "Digma",
+        "DIJITSU",
+        "DIKOM",
+        "DIMO",
+        "Dinalink",
+        "Dinax",
+        "DING DING",
+        "DIORA",
+        "DISH",
+        "Disney",
+        "Ditecma",
+        "Diva",
+        "DiverMax",
+        "Divisat",
+        "DIXON",
+        "DL",
+        "DMM",
+        "DNS",
+        "DoCoMo",
+        "Doffler",
+        "Dolamee",
+        "Dom.ru",
+        "Doogee",
+        "Doopro",
+        "Doov",
+        "Dopod",
+        "Doppio",
+        "DORLAND",
+        "Doro",
+        "DPA",
+        "DRAGON",
+        "Dragon Touch",
+        "Dreamgate",
+        "DreamStar",
+        "DreamTab",
+        "Droidlogic",
+        "Droxio",
+        "DSDevices",
+        "DSIC",
+        "Dtac",
+        "Dune HD",
+        "DUNNS Mobile",
+        "Durabook",
+        "Duubee",
+        "Dyon",
+        "E-Boda",
+        "E-Ceros",
+        "E-tel",
+        "Eagle",
+        "EagleSoar",
+        "EAS Electric",
+        "Easypix",
+        "EBEN",
+        "EBEST",
+        "Echo Mobiles",
+        "ecom",
+        "ECON",
+        "ECOO",
+        "ECS",
+        "Edenwood",
+        "EE",
+        "EFT",
+        "EGL",
+        "Einstein",
+        "EKINOX",
+        "EKO",
+        "Eks Mobility",
+        "EKT",
+        "ELARI",
+        "Elecson",
+        "Electroneum",
+        "ELECTRONIA",
+        "Elekta",
+        "Elektroland",
+        "Element",
+        "Elenberg",
+        "Elephone",
+        "Elevate",
+        "Elong Mobile",
+        "Eltex",
+        "Ematic",
+        "Emporia",
+        "ENACOM",
+        "Energizer",
+        "Energy Sistem",
+        "Engel",
+        "ENIE",
+        "Enot",
+        "eNOVA",
+        "Entity",
+        "Envizen",
+        "Ephone",
+        "Epic",
+        "Epik One",
+        "Epson",
+        "Equator",
+        "Ergo",
+        "Ericsson",
+        "Ericy",
+        "Erisson",
+        "Essential",
+        "Essentielb",
+        "eSTAR",
+        "ETOE",
+        "Eton",
+        "eTouch",
+        "Etuline",
+        "Eurocase",
+        "Eurostar",
+        "Evercoss",
+        "Everest",
+        "Everex",
+        "Evertek",
+        "Evolio",
+        "Evolveo",
+        "Evoo",
+        "EVPAD",
+        "EvroMedia",
+        "EWIS",
+        "EXCEED",
+        "Exmart",
+        "ExMobile",
+        "EXO",
+        "Explay",
+        "Express LUCK",
+        "ExtraLink",
+        "Extrem",
+        "Eyemoo",
+        "EYU",
+        "Ezio",
+        "Ezze",
+        "F&U",
+        "F+",
+        "F150",
+        "F2 Mobile",
+        "Facebook",
+        "Facetel",
+        "Facime",
+        "Fairphone",
+        "Famoco",
+        "Famous",
+        "Fantec",
+        "FaRao Pro",
+        "Farassoo",
+        "FarEasTone",
+        "Fengxiang",
+        "Fenoti",
+        "FEONAL",
+        "Fero",
+        "FFF SmartLife",
+        "Figgers",
+        "FiGi",
+        "FiGO",
+        "FiiO",
+        "Filimo",
+        "FILIX",
+        "FinePower",
+        "Finlux",
+        "FireFly Mobile",
+        "FISE",
+        "FITCO",
+        "Fluo",
+        "Fly",
+        "FLYCAT",
+        "FLYCOAY",
+        "FMT",
+        "FNB",
+        "FNF",
+        "Fobem",
+        "Fondi",
+        "Fonos",
+        "FOODO",
+        "FORME",
+        "Formuler",
+        "Forstar",
+        "Fortis",
+        "FOSSiBOT",
+        "Four Mobile",
+        "Fourel",
+        "Foxconn",
+        "FoxxD",
+        "FPT",
+        "free",
+        "Freetel",
+        "FreeYond",
+        "Frunsi",
+        "Fuego",
+        "Fujitsu",
+        "Funai",
+        "Fusion5",
+        "Future Mobile Technology",
+        "Fxtec",
+        "G-TiDE",
+        "G-Touch",
+        "Galactic",
+        "Galaxy Innovations",
+        "Gamma",
+        "Garmin-Asus",
+        "Gateway",
+        "Gazer",
+        "Geanee",
+        "Geant",
+        "Gear Mobile",
+        "Gemini",
+        "General Mobile",
+        "Genesis",
+        "GEOFOX",
+        "Geotel",
+        "Geotex",
+        "GEOZON",
+        "Getnord",
+        "GFive",
+        "Gfone",
+        "Ghia",
+        "Ghong",
+        "Ghost",
+        "Gigabyte",
+        "Gigaset",
+        "Gini",
+        "Ginzzu",
+        "Gionee",
+        "GIRASOLE",
+        "Globex",
+        "Globmall",
+        "Glofiish",
+        "GLONYX",
+        "GLX",
+        "GOCLEVER",
+        "Gocomma",
+        "GoGEN",
+        "Gol Mobile",
+        "GOLDBERG",
+        "GoldMaster",
+        "GoldStar",
+        "Goly",
+        "Gome",
+        "GoMobile",
+        "GOODTEL",
+        "Google",
+        "Goophone",
+        "Gooweel",
+        "Gplus",
+        "Gradiente",
+        "Graetz",
+        "Grape",
+        "Great Asia",
+        "Gree",
+        "Green Lion",
+        "Green Orange",
+        "Greentel",
+        "Gresso",
+        "Gretel",
+        "GroBerwert",
+        "Grundig",
+        "Gtel",
+        "GTMEDIA",
+        "GTX",
+        "Guophone",
+        "H133",
+        "H96",
+        "Hafury",
+        "Haier",
+        "Haipai",
+        "Hamlet",
+        "Hammer",
+        "Handheld",
+        "HannSpree",
+        "Hanseatic",
+        "HAOQIN",
+        "HAOVM",
+        "Hardkernel",
+        "Harper",
+        "Hartens",
+        "Hasee",
+        "Hathway",
+        "HDC",
+        "HeadWolf",
+        "HEC",
+        "Heimat",
+        "Helio",
+        "HERO",
+        "HexaByte",
+        "Hezire",
+        "Hi",
+        "Hi Nova",
+        "Hi-Level",
+        "Hiberg",
+        "HiBy",
+        "High Q",
+        "Highscreen",
+        "HiGrace",
+        "HiHi",
+        "HiKing",
+        "HiMax",
+        "HIPER",
+        "Hipstreet",
+        "Hiremco",
+        "Hisense",
+        "Hitachi",
+        "Hitech",
+        "HKC",
+        "HKPro",
+        "HLLO",
+        "HMD",
+        "HOFER",
+        "Hoffmann",
+        "HOLLEBERG",
+        "Homatics",
+        "Hometech",
+        "Homtom",
+        "Honeywell",
+        "HongTop",
+        "Hoozo",
+        "Horizon",
+        "Horizont",
+        "Hosin",
+        "Hot Pepper",
+        "Hotel",
+        "HOTREALS",
+        "Hotwav",
+        "How",
+        "HP",
+        "HTC",
+        "Huadoo",
+        "Huagan",
+        "Huavi",
+        "Huawei",
+        "Hugerock",
+        "Humax",
+        "Hurricane",
+        "Huskee",
+        "Hykker",
+        "Hyrican",
+        "Hytera",
+        "Hyundai",
+        "Hyve",
+        "i-Cherry",
+        "I-INN",
+        "i-Joy",
+        "i-mate",
+        "i-mobile",
+        "I-Plus",
+        "iBall",
+        "iBerry",
+        "ibowin",
+        "iBrit",
+        "IconBIT",
+        "iData",
+        "iDino",
+        "iDroid",
+        "iGet",
+        "iHunt",
+        "Ikea",
+        "IKI Mobile",
+        "iKoMo",
+        "iKon",
+        "iKonia",
+        "IKU Mobile",
+        "iLA",
+        "iLepo",
+        "iLife",
+        "iMan",
+        "Imaq",
+        "iMars",
+        "iMI",
+        "IMO Mobile",
+        "Imose",
+        "Impression",
+        "iMuz",
+        "iNavi",
+        "INCAR",
+        "Inch",
+        "Inco",
+        "iNew",
+        "Infiniton",
+        "Infinix",
+        "InFocus",
+        "InfoKit",
+        "Infomir",
+        "InFone",
+        "Inhon",
+        "Inka",
+        "Inkti",
+        "InnJoo",
+        "Innos",
+        "Innostream",
+        "iNo Mobile",
+        "Inoi",
+        "iNOVA",
+        "inovo",
+        "INQ",
+        "Insignia",
+        "INSYS",
+        "Intek",
+        "Intel",
+        "Intex",
+        "Invens",
+        "Inverto",
+        "Invin",
+        "iOcean",
+        "IOTWE",
+        "iOutdoor",
+        "iPEGTOP",
+        "iPro",
+        "iQ&T",
+        "IQM",
+        "IRA",
+        "Irbis",
+        "iReplace",
+        "Iris",
+        "iRobot",
+        "iRola",
+        "iRulu",
+        "iSafe Mobile",
+        "iStar",
+        "iSWAG",
+        "IT",
+        "iTel",
+        "iTruck",
+        "IUNI",
+        "iVA",
+        "iView",
+        "iVooMi",
+        "ivvi",
+        "iWaylink",
+        "iXTech",
+        "iYou",
+        "iZotron",
+        "JAY-Tech",
+        "Jedi",
+        "Jeep",
+        "Jeka",
+        "Jesy",
+        "JFone",
+        "Jiake",
+        "Jiayu",
+        "Jin Tu",
+        "Jinga",
+        "Jio",
+        "Jivi",
+        "JKL",
+        "Jolla",
+        "Joy",
+        "JoySurf",
+        "JPay",
+        "JREN",
+        "Jumper",
+        "Juniper Systems",
+        "Just5",
+        "JVC",
+        "JXD",
+        "K-Lite",
+        "K-Touch",
+        "Kaan",
+        "Kaiomy",
+        "Kalley",
+        "Kanji",
+        "Kapsys",
+        "Karbonn",
+        "Kata",
+        "KATV1",
+        "Kazam",
+        "Kazuna",
+        "KDDI",
+        "Kempler & Strauss",
+        "Kenbo",
+        "Kendo",
+        "Keneksi",
+        "KENSHI",
+        "Kenxinda",
+        "Khadas",
+        "Kiano",
+        "Kingbox",
+        "Kingstar",
+        "Kingsun",
+        "KINGZONE",
+        "Kinstone",
+        "Kiowa",
+        "Kivi",
+        "Klipad",
+        "KN Mobile",
+        "Kocaso",
+        "Kodak",
+        "Kogan",
+        "Komu",
+        "Konka",
+        "Konrow",
+        "Koobee",
+        "Koolnee",
+        "Kooper",
+        "KOPO",
+        "Koridy",
+        "Koslam",
+        "Kraft",
+        "KREZ",
+        "KRIP",
+        "KRONO",
+        "Krüger&Matz",
+        "KT-Tech",
+        "KUBO",
+        "KuGou",
+        "Kuliao",
+        "Kult",
+        "Kumai",
+        "Kurio",
+        "KVADRA",
+        "Kvant",
+        "Kydos",
+        "Kyocera",
+        "Kyowon",
+        "Kzen",
+        "KZG",
+        "L-Max",
+        "LAIQ",
+        "Land Rover",
+        "Landvo",
+        "Lanin",
+        "Lanix",
+        "Lark",
+        "Laurus",
+        "Lava",
+        "LCT",
+        "Le Pan",
+        "Leader Phone",
+        "Leagoo",
+        "Leben",
+        "LeBest",
+        "Lectrus",
+        "Ledstar",
+        "LeEco",
+        "Leelbox",
+        "Leff",
+        "Legend",
+        "Leke",
+        "Lemco",
+        "LEMFO",
+        "Lemhoov",
+        "Lenco",
+        "Lenovo",
+        "Leotec",
+        "Lephone",
+        "Lesia",
+        "Lexand",
+        "Lexibook",
+        "LG",
+        "Liberton",
+        "Lifemaxx",
+        "Lime",
+        "Lingbo",
+        "Lingwin",
+        "Linnex",
+        "Linsar",
+        "Linsay",
+        "Listo",
+        "LNMBBS",
+        "Loewe",
+        "Logic",
+        "Logic Instrument",
+        "Logicom",
+        "Logik",
+        "LOKMAT",
+        "Loview",
+        "Lovme",
+        "LPX-G",
+        "LT Mobile",
+        "Lumigon",
+        "Lumitel",
+        "Lumus",
+        "Luna",
+        "Luxor",
+        "Lville",
+        "LYF",
+        "M-Horse",
+        "M-Tech",
+        "M.T.T.",
+        "M3 Mobile",
+        "M4tel",
+        "MAC AUDIO",
+        "Macoox",
+        "Mafe",
+        "MAG",
+        "MAGCH",
+        "Magicsee",
+        "Magnus",
+        "Majestic",
+        "Malata",
+        "Mango",
+        "Manhattan",
+        "Mann",
+        "Manta Multimedia",
+        "Mantra",
+        "Mara",
+        "Marshal",
+        "Mascom",
+        "Massgo",
+        "Masstel",
+        "Master-G",
+        "Mastertech",
+        "Matco Tools",
+        "Matrix",
+        "Maunfeld",
+        "Maxcom",
+        "Maxfone",
+        "Maximus",
+        "Maxtron",
+        "MAXVI",
+        "Maxwell",
+        "Maxwest",
+        "MAXX",
+        "Maze",
+        "Maze Speed",
+        "MBI",
+        "MBK",
+        "MBOX",
+        "MDC Store",
+        "MDTV",
+        "meanIT",
+        "Mecer",
+        "Mecool",
+        "Mediacom",
+        "MediaTek",
+        "Medion",
+        "MEEG",
+        "MEGA VISION",
+        "MegaFon",
+        "Meitu",
+        "Meizu",
+        "Melrose",
+        "MeMobile",
+        "Memup",
+        "MEO",
+        "Meta",
+        "Metz",
+        "MEU",
+        "MicroMax",
+        "Microsoft",
+        "Microtech",
+        "Minix",
+        "Mint",
+        "Mintt",
+        "Mio",
+        "Mione",
+        "mipo",
+        "Miray",
+        "Mitchell & Brown",
+        "Mito",
+        "Mitsubishi",
+        "Mitsui",
+        "MIVO",
+        "MIWANG",
+        "MIXC",
+        "MiXzo",
+        "MLAB",
+        "MLLED",
+        "MLS",
+        "MMI",
+        "Mobell",
+        "Mobicel",
+        "MobiIoT",
+        "Mobiistar",
+        "Mobile Kingdom",
+        "Mobiola",
+        "Mobistel",
+        "MobiWire",
+        "Mobo",
+        "Mobvoi",
+        "Modecom",
+        "Mofut",
+        "Mosimosi",
+        "Motiv",
+        "Motorola",
+        "Movic",
+        "MOVISUN",
+        "Movitel",
+        "Moxee",
+        "mPhone",
+        "Mpman",
+        "MSI",
+        "MStar",
+        "MTC",
+        "MTN",
+        "Multilaser",
+        "MultiPOS",
+        "MwalimuPlus",
+        "MYFON",
+        "MyGica",
+        "MygPad",
+        "Mymaga",
+        "MyMobile",
+        "MyPhone",
+        "Myria",
+        "Myros",
+        "Mystery",
+        "MyTab",
+        "MyWigo",
+        "N-one",
+        "Nabi",
+        "NABO",
+        "Nanho",
+        "Naomi Phone",
+        "NASCO",
+        "National",
+        "Navcity",
+        "Navitech",
+        "Navitel",
+        "Navon",
+        "NavRoad",
+        "NEC",
+        "Necnot",
+        "Nedaphone",
+        "Neffos",
+        "NEKO",
+        "Neo",
+        "neoCore",
+        "Neolix",
+        "Neomi",
+        "Neon IQ",
+        "Neoregent",
+        "NetBox",
+        "Netgear",
+        "Netmak",
+        "NETWIT",
+        "NeuImage",
+        "NeuTab",
+        "NEVIR",
+        "New Balance",
+        "New Bridge",
+        "Newgen",
+        "Newland",
+        "Newman",
+        "Newsday",
+        "NewsMy",
+        "Nexa",
+        "NEXBOX",
+        "Nexian",
+        "NEXON",
+        "NEXT",
+        "Next & NextStar",
+        "Nextbit",
+        "NextBook",
+        "NextTab",
+        "NG Optics",
+        "NGM",
+        "NGpon",
+        "Nikon",
+        "NINETEC",
+        "Nintendo",
+        "nJoy",
+        "NOA",
+        "Noain",
+        "Nobby",
+        "Noblex",
+        "NOBUX",
+        "noDROPOUT",
+        "NOGA",
+        "Nokia",
+        "Nomi",
+        "Nomu",
+        "Noontec",
+        "Nordmende",
+        "NORMANDE",
+        "NorthTech",
+        "Nos",
+        "Nothing Phone",
+        "Nous",
+        "Novacom",
+        "Novex",
+        "Novey",
+        "NoviSea",
+        "NOVO",
+        "NTT West",
+        "NuAns",
+        "Nubia",
+        "NUU Mobile",
+        "NuVision",
+        "Nuvo",
+        "Nvidia",
+        "NYX Mobile",
+        "O+",
+        "O2",
+        "Oale",
+        "Oangcc",
+        "OASYS",
+        "Obabox",
+        "Ober",
+        "Obi",
+        "OCEANIC",
+        "Odotpad",
+        "Odys",
+        "OINOM",
+        "Ok",
+        "Okapia",
+        "Oking",
+        "OKSI",
+        "OKWU",
+        "Olax",
+        "Olkya",
+        "Ollee",
+        "OLTO",
+        "Olympia",
+        "OMIX",
+        "Onda",
+        "OneClick",
+        "OneLern",
+        "OnePlus",
+        "Onida",
+        "Onix",
+        "Onkyo",
+        "ONN",
+        "ONVO",
+        "ONYX BOOX",
+        "Ookee",
+        "Ooredoo",
+        "OpelMobile",
+        "Openbox",
+        "Ophone",
+        "OPPO",
+        "Opsson",
+        "Optoma",
+        "Orange",
+        "Orange Pi",
+        "Orava",
+        "Orbic",
+        "Orbita",
+        "Orbsmart",
+        "Ordissimo",
+        "Orion",
+        "OSCAL",
+        "OTTO",
+        "OUJIA",
+        "Ouki",
+        "Oukitel",
+        "OUYA",
+        "Overmax",
+        "Ovvi",
+        "Owwo",
+        "OYSIN",
+        "Oysters",
+        "Oyyu",
+        "OzoneHD",
+        "P-UP",
+        "Pacific Research Alliance",
+        "Packard Bell",
+        "Padpro",
+        "PAGRAER",
+        "Paladin",
+        "Palm",
+        "Panacom",
+        "Panasonic",
+        "Panavox",
+        "Pano",
+        "Panodic",
+        "Panoramic",
+        "Pantech",
+        "PAPYRE",
+        "Parrot Mobile",
+        "Partner Mobile",
+        "PC Smart",
+        "PCBOX",
+        "PCD",
+        "PCD Argentina",
+        "PEAQ",
+        "Pelitt",
+        "Pendoo",
+        "Penta",
+        "Pentagram",
+        "Perfeo",
+        "Phicomm",
+        "Philco",
+        "Philips",
+        "Phonemax",
+        "phoneOne",
+        "Pico",
+        "PINE",
+        "Pioneer",
+        "Pioneer Computers",
+        "PiPO",
+        "PIRANHA",
+        "Pixela",
+        "Pixelphone",
+        "Pixus",
+        "Planet Computers",
+        "Platoon",
+        "Play Now",
+        "Ployer",
+        "Plum",
+        "PlusStyle",
+        "Pluzz",
+        "PocketBook",
+        "POCO",
+        "Point Mobile",
+        "Point of View",
+        "Polar",
+        "PolarLine",
+        "Polaroid",
+        "Polestar",
+        "PolyPad",
+        "Polytron",
+        "Pomp",
+        "Poppox",
+        "POPTEL",
+        "Porsche",
+        "Positivo",
+        "Positivo BGH",
+        "PPTV",
+        "Premier",
+        "Premio",
+        "Prestigio",
+        "PRIME",
+        "Primepad",
+        "Primux",
+        "Pritom",
+        "Prixton",
+        "PROFiLO",
+        "Proline",
+        "Prology",
+        "ProScan",
+        "PROSONIC",
+        "Protruly",
+        "ProVision",
+        "PULID",
+        "Punos",
+        "Purism",
+        "Q-Box",
+        "Q-Touch",
+        "Q.Bell",
+        "QFX",
+        "Qilive",
+        "QIN",
+        "QLink",
+        "QMobile",
+        "Qnet Mobile",
+        "QTECH",
+        "Qtek",
+        "Quantum",
+        "Quatro",
+        "Qubo",
+        "Quechua",
+        "Quest",
+        "Quipus",
+        "Qumo",
+        "Qware",
+        "QWATT",
+        "R-TV",
+        "Rakuten",
+        "Ramos",
+        "Raspberry",
+        "Ravoz",
+        "Raylandz",
+        "Razer",
+        "RCA Tablets",
+        "Reach",
+        "Readboy",
+        "Realme",
+        "RED",
+        "Redbean",
+        "Redfox",
+        "RedLine",
+        "Redway",
+        "Reeder",
+        "REGAL",
+        "RelNAT",
+        "Relndoo",
+        "Remdun",
+        "Renova",
+        "rephone",
+        "Retroid Pocket",
+        "Revo",
+        "Revomovil",
+        "Rhino",
+        "Ricoh",
+        "Rikomagic",
+        "RIM",
+        "Rinno",
+        "Ritmix",
+        "Ritzviva",
+        "Riviera",
+        "Rivo",
+        "Rizzen",
+        "ROADMAX",
+        "Roadrover",
+        "Roam Cat",
+        "Rocket",
+        "ROiK",
+        "Rokit",
+        "Roku",
+        "Rombica",
+        "Ross&Moor",
+        "Rover",
+        "RoverPad",
+        "Royole",
+        "RoyQueen",
+        "RT Project",
+        "RugGear",
+        "RuggeTech",
+        "Ruggex",
+        "Ruio",
+        "Runbo",
+        "Rupa",
+        "Ryte",
+        "S-Color",
+        "S-TELL",
+        "S2Tel",
+        "Saba",
+        "Safaricom",
+        "Sagem",
+        "Sagemcom",
+        "Saiet",
+        "SAILF",
+        "Salora",
+        "Samsung",
+        "Samtech",
+        "Samtron",
+        "Sanei",
+        "Sankey",
+        "Sansui",
+        "Santin",
+        "SANY",
+        "Sanyo",
+        "Savio",
+        "Sber",
+        "SCBC",
+        "Schneider",
+        "Schok",
+        "Scoole",
+        "Scosmos",
+        "Seatel",
+        "SEBBE",
+        "Seeken",
+        "SEEWO",
+        "SEG",
+        "Sega",
+        "SEHMAX",
+        "Selecline",
+        "Selenga",
+        "Selevision",
+        "Selfix",
+        "SEMP TCL",
+        "Sencor",
+        "Sendo",
+        "Senkatel",
+        "Senseit",
+        "Senwa",
+        "Seuic",
+        "Sewoo",
+        "SFR",
+        "SGIN",
+        "Shanling",
+        "Sharp",
+        "Shift Phones",
+        "Shivaki",
+        "Shtrikh-M",
+        "Shuttle",
+        "Sico",
+        "Siemens",
+        "Sigma",
+        "Silelis",
+        "Silent Circle",
+        "Silva Schneider",
+        "Simbans",
+        "simfer",
+        "Simply",
+        "Singtech",
+        "Siragon",
+        "Sirin Labs",
+        "SK Broadband",
+        "SKG",
+        "SKK Mobile",
+        "Sky",
+        "Skyline",
+        "SkyStream",
+        "Skytech",
+        "Skyworth",
+        "Smadl",
+        "Smailo",
+        "Smart",
+        "Smart Electronic",
+        "Smart Kassel",
+        "Smartab",
+        "SmartBook",
+        "SMARTEC",
+        "Smartex",
+        "Smartfren",
+        "Smartisan",
+        "Smarty",
+        "Smooth Mobile",
+        "Smotreshka",
+        "SMUX",
+        "SNAMI",
+        "SobieTech",
+        "Soda",
+        "Softbank",
+        "Soho Style",
+        "Solas",
+        "SOLE",
+        "SOLO",
+        "Solone",
+        "Sonim",
+        "SONOS",
+        "Sony",
+        "Sony Ericsson",
+        "SOSH",
+        "SoulLink",
+        "Soundmax",
+        "Soyes",
+        "Spark",
+        "Sparx",
+        "SPC",
+        "Spectralink",
+        "Spectrum",
+        "Spice",
+        "Sprint",
+        "SPURT",
+        "SQOOL",
+        "SSKY",
+        "Star",
+        "Starlight",
+        "Starmobile",
+        "Starway",
+        "Starwind",
+        "STF Mobile",
+        "STG Telecom",
+        "STK",
+        "Stonex",
+        "Storex",
+        "StrawBerry",
+        "Stream",
+        "STRONG",
+        "Stylo",
+        "Subor",
+        "Sugar",
+        "Sumvision",
+        "Sunmax",
+        "Sunmi",
+        "Sunny",
+        "Sunstech",
+        "SunVan",
+        "Sunvell",
+        "SUNWIND",
+        "Super General",
+        "SuperBOX",
+        "SuperSonic",
+        "SuperTab",
+        "Supra",
+        "Supraim",
+        "Surfans",
+        "Surge",
+        "Suzuki",
+        "Sveon",
+        "Swipe",
+        "SWISSMOBILITY",
+        "Swisstone",
+        "Switel",
+        "Syco",
+        "SYH",
+        "Sylvania",
+        "Symphony",
+        "Syrox",
+        "System76",
+        "T-Mobile",
+        "T96",
+        "TADAAM",
+        "TAG Tech",
+        "Taiga System",
+        "Takara",
+        "Talius",
+        "Tambo",
+        "Tanix",
+        "TAUBE",
+        "TB Touch",
+        "TCL",
+        "TD Systems",
+        "TD Tech",
+        "TeachTouch",
+        "Technicolor",
+        "Technika",
+        "TechniSat",
+        "Technopc",
+        "TECHNOSAT",
+        "TechnoTrend",
+        "TechPad",
+        "TechSmart",
+        "Techstorm",
+        "Techwood",
+        "Teclast",
+        "Tecno Mobile",
+        "TecToy",
+        "TEENO",
+        "Teknosa",
+        "Tele2",
+        "Telefunken",
+        "Telego",
+        "Telenor",
+        "Telia",
+        "Telit",
+        "Telkom",
+        "Telly",
+        "Telma",
+        "TeloSystems",
+        "Telpo",
+        "TENPLUS",
+        "Teracube",
+        "Tesco",
+        "Tesla",
+        "TETC",
+        "Tetratab",
+        "teXet",
+        "ThL",
+        "Thomson",
+        "Thuraya",
+        "TIANYU",
+        "Tibuta",
+        "Tigers",
+        "Time2",
+        "Timovi",
+        "TIMvision",
+        "Tinai",
+        "Tinmo",
+        "TiPhone",
+        "TiVo",
+        "TJC",
+        "TJD",
+        "TOKYO",
+        "Tolino",
+        "Tone",
+        "TOOGO",
+        "Tooky",
+        "Top House",
+        "TopDevice",
+        "TOPDON",
+        "Topelotek",
+        "Toplux",
+        "TOPSHOWS",
+        "Topsion",
+        "Topway",
+        "Torex",
+        "Torque",
+        "TOSCIDO",
+        "Toshiba",
+        "Touch Plus",
+        "Touchmate",
+        "TOX",
+        "TPS",
+        "Transpeed",
+        "TrekStor",
+        "Trevi",
+        "TriaPlay",
+        "Trident",
+        "Trifone",
+        "Trio",
+        "Tronsmart",
+        "True",
+        "True Slim",
+        "Tsinghua Tongfang",
+        "TTEC",
+        "TTfone",
+        "TTK-TV",
+        "TuCEL",
+        "Tunisie Telecom",
+        "Turbo",
+        "Turbo-X",
+        "TurboKids",
+        "TurboPad",
+        "Turkcell",
+        "Tuvio",
+        "TVC",
+        "TwinMOS",
+        "TWM",
+        "Twoe",
+        "TWZ",
+        "TYD",
+        "Tymes",
+        "Türk Telekom",
+        "U-Magic",
+        "U.S. Cellular",
+        "UD",
+        "UE",
+        "UGINE",
+        "Ugoos",
+        "Uhans",
+        "Uhappy",
+        "Ulefone",
+        "Umax",
+        "UMIDIGI",
+        "Unblock Tech",
+        "Uniden",
+        "Unihertz",
+        "Unimax",
+        "Uniqcell",
+        "Uniscope",
+        "Unistrong",
+        "Unitech",
+        "UNIWA",
+        "Unnecto",
+        "Unnion Technologies",
+        "UNNO",
+        "Unonu",
+        "Unowhy",
+        "UOOGOU",
+        "Urovo",
+        "UTime",
+        "UTOK",
+        "UTStarcom",
+        "UZ Mobile",
+        "V-Gen",
+        "V-HOME",
+        "V-HOPE",
+        "v-mobile",
+        "VAIO",
+        "VALE",
+        "VALEM",
+        "VALTECH",
+        "VANGUARD",
+        "Vankyo",
+        "VANWIN",
+        "Vargo",
+        "Vastking",
+        "VAVA",
+        "VC",
+        "VDVD",
+        "Vega",
+        "Vekta",
+        "Venso",
+        "Venstar",
+        "Venturer",
+        "VEON",
+        "Verico",
+        "Verizon",
+        "Vernee",
+        "Verssed",
+        "Versus",
+        "Vertex",
+        "Vertu",
+        "Verykool",
+        "Vesta",
+        "Vestel",
+        "VETAS",
+        "Vexia",
+        "VGO TEL",
+        "ViBox",
+        "Victurio",
+        "VIDA",
+        "Videocon",
+        "Videoweb",
+        "ViewSonic",
+        "VIIPOO",
+        "VIKUSHA",
+        "VILLAON",
+        "Vinabox",
+        "Vinga",
+        "Vinsoc",
+        "Vios",
+        "Viper",
+        "Vipro",
+        "Virzo",
+        "Vision Touch",
+        "Visual Land",
+        "Vitelcom",
+        "Vityaz",
+        "Viumee",
+        "Vivax",
+        "VIVIMAGE",
+        "Vivo",
+        "VIWA",
+        "Vizio",
+        "Vizmo",
+        "VK Mobile",
+        "VKworld",
+        "VNPT Technology",
+        "VOCAL",
+        "Vodacom",
+        "Vodafone",
+        "VOGA",
+        "VOLIA",
+        "VOLKANO",
+        "Volla",
+        "Volt",
+        "Vonino",
+        "Vontar",
+        "Vorago",
+        "Vorcom",
+        "Vorke",
+        "Vormor",
+        "Vortex",
+        "Voto",
+        "VOX",
+        "Voxtel",
+        "Voyo",
+        "Vsmart",
+        "Vsun",
+        "VUCATIMES",
+        "Vue Micro",
+        "Vulcan",
+        "VVETIME",
+        "Völfen",
+        "W&O",
+        "WAF",
+        "Wainyok",
+        "Walker",
+        "Walton",
+        "Waltter",
+        "Wanmukang",
+        "WANSA",
+        "WE",
+        "We. by Loewe.",
+        "Webfleet",
+        "WeChip",
+        "Wecool",
+        "Weelikeit",
+        "Weimei",
+        "WellcoM",
+        "WELLINGTON",
+        "Western Digital",
+        "Westpoint",
+        "Wexler",
+        "White Mobile",
+        "Whoop",
+        "Wieppo",
+        "Wigor",
+        "Wiko",
+        "Wileyfox",
+        "Winds",
+        "Wink",
+        "Winmax",
+        "Winnovo",
+        "Winstar",
+        "Wintouch",
+        "Wiseasy",
+        "WIWA",
+        "WizarPos",
+        "Wizz",
+        "Wolder",
+        "Wolfgang",
+        "Wolki",
+        "WONDER",
+        "Wonu",
+        "Woo",
+        "Wortmann",
+        "Woxter",
+        "WS",
+        "X-AGE",
+        "X-BO",
+        "X-Mobile",
+        "X-TIGI",
+        "X-View",
+        "X.Vision",
+        "X88",
+        "X96",
+        "X96Q",
+        "Xcell",
+        "XCOM",
+        "Xcruiser",
+        "XElectron",
+        "XGEM",
+        "XGIMI",
+        "Xgody",
+        "Xiaodu",
+        "Xiaolajiao",
+        "Xiaomi",
+        "Xion",
+        "Xolo",
+        "Xoro",
+        "Xshitou",
+        "Xsmart",
+        "Xtouch",
+        "Xtratech",
+        "Xwave",
+        "XY Auto",
+        "Yandex",
+        "Yarvik",
+        "YASIN",
+        "YELLYOUTH",
+        "YEPEN",
+        "Yes",
+        "Yestel",
+        "Yezz",
+        "Yoka TV",
+        "Yooz",
+        "Yota",
+        "YOTOPT",
+        "Youin",
+        "Youwei",
+        "Ytone",
+        "Yu",
+        "YU Fly",
+        "Yuandao",
+        "YUHO",
+        "YUMKEM",
+        "YUNDOO",
+        "Yuno",
+        "YunSong",
+        "Yusun",
+        "Yxtel",
+        "Z-Kai",
+        "Zaith",
+        "Zamolxe",
+        "Zatec",
+        "Zealot",
+        "Zeblaze",
+        "Zebra",
+        "Zeeker",
+        "Zeemi",
+        "Zen",
+        "Zenek",
+        "Zentality",
+        "Zfiner",
+        "ZH&K",
+        "Zidoo",
+        "ZIFRO",
+        "Zigo",
+        "ZIK",
+        "Zinox",
+        "Ziox",
+        "Zonda",
+        "Zonko",
+        "Zoom",
+        "ZoomSmart",
+        "Zopo",
+        "ZTE",
+        "Zuum",
+        "Zync",
+        "ZYQ",
+        "Zyrex",
+        "öwn",
+    ]
+}
@@ -0,0 +1,8 @@
+// Generated by copy_static_files.sh from the `$deviceBrands` table in
+// upstream's Parser/Device/AbstractDeviceParser.php, don't edit by hand.
+//
+// It has not been imported yet, so no brand has a short code.
+
+pub(super) fn brand_codes() -> Vec<(&'static str, &'static str)> {
+    vec![]
+}
@@ -7,13 +7,14 @@ use serde::Serialize;
 // to make use of it while having any hope of keeping it up to date with changes upstream.
 
 pub struct AvailableBrowsers {
-    // TODO this may not ever be needed.
-    //    browsers_by_short: HashMap<String, AvailableBrowser>,
+    browsers_by_short: HashMap<String, AvailableBrowser>,
     browsers_by_name: HashMap<String, AvailableBrowser>,
 }
 
 #[derive(Clone, Debug, Serialize)]
 pub struct AvailableBrowser {
+    /// Matomo's code for this browser, such as `CH` for Chrome.
+    pub short_name: String,
     pub name: String,
     pub family: Option<String>,
     #[serde(skip)]
@@ -41,8 +42,9 @@ impl Default for AvailableBrowsers {
                 let mobile_only = mobile_only_browsers.contains(&short);
                 let family = browser_families.get(&short).cloned();
                 (
-                    short,
+                    short.clone(),
                     AvailableBrowser {
+                        short_name: short,
                         name: browser,
                         mobile_only,
                         family,
@@ -62,13 +64,18 @@ impl Default for AvailableBrowsers {
             .collect::<HashMap<String, AvailableBrowser>>();
 
         Self {
-            //browsers_by_short,
+            browsers_by_short,
             browsers_by_name,
         }
     }
 }
 
 impl AvailableBrowsers {
+    /// Looks up a browser by its short code, such as `CH`.
+    pub fn search_by_short(&self, short: &str) -> Option<&AvailableBrowser> {
+        self.browsers_by_short.get(short)
+    }
+
     pub fn search_by_name(&self, name: &str) -> Option<&AvailableBrowser> {
         let name = name.to_lowercase().replace(' ', "");
         self.browsers_by_name
@@ -2,13 +2,14 @@ use std::collections::HashMap;
 use std::collections::HashSet;
 
 pub struct AvailableOSs {
-    // TODO this may not ever be needed.
-    #[allow(dead_code)]
+    oss_by_short: HashMap<String, AvailableOS>,
     oss_by_name: HashMap<String, AvailableOS>,
 }
 
 #[derive(Clone, Debug)]
 pub struct AvailableOS {
+    /// Matomo's code for this os, such as `AND` for Android.
+    pub short_name: String,
     pub name: String,
     pub desktop: bool,
     pub family: Option<String>,
@@ -41,8 +42,9 @@ impl Default for AvailableOSs {
                 };
 
                 (
-                    short,
+                    short.clone(),
                     AvailableOS {
+                        short_name: short,
                         name: os,
                         desktop,
                         family,
@@ -56,7 +58,10 @@ impl Default for AvailableOSs {
             .map(|os| (os.name.replace(' ', "").to_lowercase(), os.clone()))
             .collect::<HashMap<String, AvailableOS>>();
 
-        Self { oss_by_name }
+        Self {
+            oss_by_short,
+            oss_by_name,
+        }
     }
 }
 
@@ -65,6 +70,11 @@ impl AvailableOSs {
         let name = name.to_lowercase().replace(' ', "");
         self.oss_by_name.get(&name)
     }
+
+    /// Looks up an os by its short code, such as `AND`.
+    pub fn search_by_short(&self, short: &str) -> Option<&AvailableOS> {
+        self.oss_by_short.get(short)
+    }
 }
 
 fn available_operating_systems() -> HashMap<String, String> {
@@ -1,10 +1,16 @@
+pub mod cache;
 pub mod client_hints;
 pub mod device_detector;
+pub mod diagnostics;
+pub mod error;
 #[cfg(feature = "build-binary")]
 pub mod http;
+pub mod known_brands;
 pub mod known_browsers;
 pub mod known_oss;
+pub mod options;
 pub mod parsers;
+mod pool;
 
 #[cfg(feature = "ffi")]
 pub mod ffi;
@@ -5,13 +5,22 @@
 // static GLOBAL: Allocator<System> = Allocator::system();
 
 // use std::env;
+use std::io::{BufRead, Read, Write};
 use std::process::ExitCode;
 
-use clap::{ArgAction, Parser};
+use clap::{ArgAction, Parser, ValueEnum};
+use indexmap::IndexMap;
+use serde::Deserialize;
+use serde_json::Value;
 use std::net::{IpAddr, SocketAddr};
+use std::path::PathBuf;
+use std::time::Duration;
 
-use rust_device_detector::device_detector::DeviceDetector;
-use rust_device_detector::http::server;
+use rust_device_detector::client_hints::parse_header_block;
+use rust_device_detector::device_detector::{Detection, DeviceDetector};
+use rust_device_detector::error::Error;
+use rust_device_detector::http::{server_with_options, Limits, Listen, Options, Reload};
+use rust_device_detector::options::{DetectionOptions, VersionTruncation};
 
 #[derive(Parser, Debug)]
 /// A commandline user agent detection tool
@@ -23,10 +32,32 @@ struct Args {
     ///
     /// In interactive mode, each stdin line will be parsed
     /// as a user agent, and we will return on stout, one single
-    /// line of json as a result.
+    /// line of json as a result. See --input-format for sending
+    /// headers too.
     #[arg(short = 'i', long = "interactive")]
     interactive: bool,
 
+    /// What each request is in interactive mode.
+    #[arg(
+        long = "input-format",
+        value_enum,
+        default_value = "ua",
+        requires = "interactive"
+    )]
+    input_format: InputFormat,
+
+    /// Run in framed mode, for when client hints need to be sent too, or
+    /// results need to be matched up with requests.
+    ///
+    /// Each request on stdin is a json object, `{"ua": ..., "headers": {...},
+    /// "id": ...}`, on a line of its own. Alternatively, a line with just the
+    /// length of the request in bytes can come first, after which the request
+    /// needn't be on one line. Each result on stdout is one line of json, with
+    /// the `id` of its request, or the position of the request counting from 1
+    /// if it didn't have one.
+    #[arg(long = "framed", conflicts_with_all(["interactive", "server"]))]
+    framed: bool,
+
     /// Run as an http server.
     ///
     /// This will run the command as an http server, listening on the
@@ -48,6 +79,102 @@ struct Args {
     #[arg(short = 'p', long = "port", default_value = "8080")]
     port: u16,
 
+    /// Listen on a unix domain socket at this path instead of an address and
+    /// port, when in http server mode.
+    #[cfg(unix)]
+    #[arg(
+        long = "unix-socket",
+        value_name = "PATH",
+        requires = "server",
+        conflicts_with_all(["ip", "port"])
+    )]
+    unix_socket: Option<PathBuf>,
+
+    /// Load the regexes from this directory instead of the ones compiled in.
+    ///
+    /// The directory must have the same layout as `regexes/` in this repository
+    /// (or upstream), so a new upstream release can be used without rebuilding.
+    #[arg(short = 'r', long = "regexes", value_name = "DIR")]
+    regexes: Option<PathBuf>,
+
+    /// When in http server mode, reload the regexes whenever the directory
+    /// given with --regexes changes.
+    ///
+    /// Reloads can also be triggered with SIGHUP, which is enabled whenever
+    /// --regexes is given, or with `POST /reload`, see --reload-token-file.
+    #[arg(long = "watch", requires = "regexes")]
+    watch: bool,
+
+    /// How often to check the regex directory for changes, in seconds.
+    #[arg(long = "watch-interval", default_value = "5", value_name = "SECONDS")]
+    watch_interval: u64,
+
+    /// Enable `POST /reload`, for requests with `Authorization: Bearer <token>`,
+    /// where the token is the first line of FILE.
+    #[arg(long = "reload-token-file", requires = "regexes", value_name = "FILE")]
+    reload_token_file: Option<PathBuf>,
+
+    /// A file of user agents, one per line, which must all parse before
+    /// reloaded regexes replace the running ones.
+    #[arg(long = "smoke-corpus", value_name = "FILE")]
+    smoke_corpus: Option<PathBuf>,
+
+    /// Compile every regex up front, using all cpus, rather than as each is
+    /// first needed.
+    ///
+    /// In server mode, this is done before accepting any connections, and
+    /// again for every reload, so that no request has to wait on compilation.
+    #[arg(long = "warm")]
+    warm: bool,
+
+    /// When in http server mode, the largest request body accepted, in bytes.
+    ///
+    /// Larger requests get a 413. Newline delimited batches are streamed, so
+    /// for those this is the longest line accepted instead.
+    #[arg(
+        long = "max-body-bytes",
+        default_value = "16777216",
+        value_name = "BYTES"
+    )]
+    max_body_bytes: usize,
+
+    /// When in http server mode, how long a request may take before it gets
+    /// a 408, in seconds. Reloads are not limited.
+    #[arg(long = "request-timeout", default_value = "30", value_name = "SECONDS")]
+    request_timeout: u64,
+
+    /// When in http server mode, how many requests may be handled at once.
+    /// Any more get a 503.
+    #[arg(
+        long = "max-concurrent-requests",
+        default_value = "1024",
+        value_name = "REQUESTS"
+    )]
+    max_concurrent_requests: usize,
+
+    /// When in http server mode, how long a client has to send its request
+    /// headers before the connection is closed, in seconds.
+    #[arg(
+        long = "header-read-timeout",
+        default_value = "10",
+        value_name = "SECONDS"
+    )]
+    header_read_timeout: u64,
+
+    /// When in http server mode, close connections after each request rather
+    /// than keeping them open for the next one.
+    #[arg(long = "no-keep-alive")]
+    no_keep_alive: bool,
+
+    /// When in http server mode, how long to wait on SIGTERM or ctrl-c for
+    /// requests in flight to finish before exiting anyway, in seconds.
+    #[arg(
+        long = "shutdown-timeout",
+        default_value = "30",
+        value_name = "SECONDS"
+    )]
+    shutdown_timeout: u64,
+
     #[cfg(feature = "cache")]
     /// If set, how many entries to cache in an lru cache.
     ///
@@ -57,20 +184,56 @@ struct Args {
     #[arg(short = 'c', long = "cache", default_value = None, value_name = "ENTRIES")]
     cache: Option<u64>,
 
+    /// Keep detections in a file in this directory, so they carry over from
+    /// one run to the next. They are only reused with the same regexes, the
+    /// file for any others is deleted. Takes the place of `--cache`.
+    #[arg(long = "cache-dir", value_name = "DIR")]
+    cache_dir: Option<PathBuf>,
+
+    /// How much of the client, engine and os versions to report: none (the
+    /// whole version), major, minor, patch or build. In server mode, it can
+    /// also be given per request as `?version_truncation=...`.
+    #[arg(
+        long = "version-truncation",
+        default_value = "none",
+        value_name = "PARTS"
+    )]
+    version_truncation: VersionTruncation,
+
+    /// Don't look for bots, detect the client, device and os of every user
+    /// agent instead.
+    #[arg(long = "skip-bot-detection")]
+    skip_bot_detection: bool,
+
+    /// Detect the client, device and os that bots claim to be as well, and
+    /// report them alongside the bot.
+    #[arg(long = "detect-bot-devices")]
+    detect_bot_devices: bool,
+
+    /// Report bots as just `{"bot": true}`, without saying which bot.
+    #[arg(long = "discard-bot-information")]
+    discard_bot_information: bool,
+
     /// When in cli mode (the default) this is the user agent to parse.
     ///
     /// Always remember escape shell arguments!
-    #[arg(required_unless_present_any(["interactive", "server"]))]
+    #[arg(required_unless_present_any(["interactive", "server", "framed", "headers_file", "header"]))]
     useragent: Option<String>,
 
-    // TODO we need to be able to just pass in a big block of headers as a single parameter
-    // so to make it easier to use without having to know headers ahead of time.
-    // we can even still cache if we filter out irrelevant headers and sort them.
-    /// Additional individual headers to pass to the detector. The user agent will NOT
-    /// be detected by passing it in this option. Furthermore, the cache will not be
-    /// bypassed when using this option because headers change all the time.
+    /// A file with a block of http headers to pass to the detector, one
+    /// `Name: value` per line, or `-` to read them from stdin.
+    ///
+    /// If no user agent is given, the `User-Agent` header is used instead, so
+    /// the headers of a request can be passed in just as they are.
+    #[arg(long = "headers", alias = "headers-file", value_name = "FILE")]
+    headers_file: Option<PathBuf>,
+
+    /// A single header to pass to the detector, as `Name: value`, which can be
+    /// given more than once. Like with `--headers`, a `User-Agent` header
+    /// is detected when no user agent is given. The client hints among them
+    /// are cached along with the user agent, so they don't get mixed up.
     #[arg(short = 'H', long = "header", action = ArgAction::Append, value_name = "HEADER")]
-    headers: Option<Vec<String>>,
+    header: Option<Vec<String>>,
 
     /// Generate a basic test cases instead of the normal output.
     ///
@@ -82,6 +245,215 @@ struct Args {
     gen_test_case: bool,
 }
 
+/// What each request is in interactive mode.
+#[derive(Clone, Copy, Debug, ValueEnum)]
+enum InputFormat {
+    /// A user agent, one per line.
+    Ua,
+    /// json objects like `{"user_agent": ..., "headers": {...}}`, which needn't
+    /// each be on a line of their own, or arrays of them.
+    Json,
+    /// A json object like the above on each line.
+    Ndjson,
+    /// Blocks of http headers, including `User-Agent`, one `Name: value` per
+    /// line and separated by blank lines.
+    RawHeaders,
+}
+
+/// A request in framed mode, or in interactive mode with json input.
+#[derive(Deserialize)]
+struct JsonRequest {
+    #[serde(alias = "user_agent")]
+    ua: String,
+    #[serde(default)]
+    headers: IndexMap<String, String>,
+}
+
+/// Detects a single framed request, tagged with its id.
+fn framed_response(detector: &DeviceDetector, frame: &[u8], position: u64) -> Value {
+    let mut request: Value = match serde_json::from_slice(frame) {
+        Ok(request) => request,
+        Err(err) => return tagged(position.into(), invalid_request(err)),
+    };
+
+    let id = match request.get_mut("id") {
+        Some(id) => id.take(),
+        None => position.into(),
+    };
+
+    let request = match JsonRequest::deserialize(request) {
+        Ok(request) => request,
+        Err(err) => return tagged(id, invalid_request(err)),
+    };
+
+    let headers = request.headers.into_iter().collect();
+    let value = match detector.parse(&request.ua, Some(headers)) {
+        Ok(detection) => detection.to_value(),
+        Err(err) => err.to_value(),
+    };
+
+    tagged(id, value)
+}
+
+fn invalid_request(err: impl std::fmt::Display) -> Value {
+    serde_json::json!({
+        "error": {
+            "kind": "invalid_request",
+            "message": err.to_string(),
+        }
+    })
+}
+
+fn tagged(id: Value, mut value: Value) -> Value {
+    if let Value::Object(map) = &mut value {
+        map.insert("id".to_owned(), id);
+    }
+    value
+}
+
+fn print_detection(ua: &str, detection: Result<Detection, Error>, gen_test_case: bool) {
+    match detection {
+        Ok(detection) if gen_test_case => println!("{}", detection.to_test_case(ua)),
+        Ok(detection) => println!("{}", detection.to_value()),
+        Err(err) => println!("{}", err.to_value()),
+    }
+}
+
+fn detect_json(detector: &DeviceDetector, request: Value, gen_test_case: bool) {
+    let request = match JsonRequest::deserialize(request) {
+        Ok(request) => request,
+        Err(err) => return println!("{}", invalid_request(err)),
+    };
+
+    let headers = request.headers.into_iter().collect();
+    let detection = detector.parse(&request.ua, Some(headers));
+    print_detection(&request.ua, detection, gen_test_case);
+}
+
+fn detect_header_block(detector: &DeviceDetector, block: &[u8], gen_test_case: bool) {
+    let headers = std::str::from_utf8(block)
+        .map_err(Error::from)
+        .and_then(parse_header_block);
+    let headers = match headers {
+        Ok(headers) => headers,
+        Err(err) => return println!("{}", err.to_value()),
+    };
+
+    let ua = headers
+        .iter()
+        .find(|(name, _)| name.eq_ignore_ascii_case("user-agent"))
+        .map(|(_, ua)| ua.clone());
+    let Some(ua) = ua else {
+        return println!("{}", invalid_request("no User-Agent header"));
+    };
+
+    let detection = detector.parse(&ua, Some(headers));
+    print_detection(&ua, detection, gen_test_case);
+}
+
+/// Runs interactive mode until stdin is closed. Every request gets a line out,
+/// a result or an error, in order, except that in json mode invalid json ends
+/// it, as there's no telling where the next request would start.
+fn interactive(
+    detector: &DeviceDetector,
+    format: InputFormat,
+    gen_test_case: bool,
+) -> std::io::Result<()> {
+    let mut stdin = std::io::stdin().lock();
+
+    if let InputFormat::Json = format {
+        for request in serde_json::Deserializer::from_reader(stdin).into_iter() {
+            match request? {
+                Value::Array(requests) => {
+                    for request in requests {
+                        detect_json(detector, request, gen_test_case);
+                    }
+                }
+                request => detect_json(detector, request, gen_test_case),
+            }
+        }
+        return Ok(());
+    }
+
+    let mut line = Vec::with_capacity(50); // may also use with_capacity if you can guess
+    let mut block = Vec::new();
+    loop {
+        line.clear(); // clear to reuse the buffer
+        let read = stdin.read_until(b'\n', &mut line)?;
+        let blank = line.iter().all(u8::is_ascii_whitespace);
+
+        match format {
+            InputFormat::Ua if read > 0 => {
+                // a line that fails gets an error object in place of its
+                // result, so every line in still has a line out.
+                match std::str::from_utf8(&line) {
+                    Ok(ua) => {
+                        let ua = ua.trim_end();
+                        print_detection(ua, detector.parse(ua, None), gen_test_case)
+                    }
+                    Err(err) => println!("{}", Error::from(err).to_value()),
+                }
+            }
+
+            InputFormat::Ndjson if read > 0 && !blank => match serde_json::from_slice(&line) {
+                Ok(request) => detect_json(detector, request, gen_test_case),
+                Err(err) => println!("{}", invalid_request(err)),
+            },
+
+            InputFormat::RawHeaders if blank && !block.is_empty() => {
+                detect_header_block(detector, &block, gen_test_case);
+                block.clear();
+            }
+            InputFormat::RawHeaders if !blank => block.extend_from_slice(&line),
+
+            _ => (),
+        }
+
+        if read == 0 {
+            return Ok(());
+        }
+    }
+}
+
+/// Runs framed mode until stdin is closed, see [`Args::framed`].
+fn framed(detector: &DeviceDetector) -> std::io::Result<()> {
+    let mut stdin = std::io::stdin().lock();
+    let mut stdout = std::io::stdout().lock();
+    let mut frame = Vec::new();
+    let mut position = 0;
+
+    loop {
+        frame.clear();
+        if stdin.read_until(b'\n', &mut frame)? == 0 {
+            return Ok(());
+        }
+        if frame.iter().all(u8::is_ascii_whitespace) {
+            continue;
+        }
+        position += 1;
+
+        // no request is a bare number, so this is the length of the next one
+        let line = frame.trim_ascii();
+        if line.iter().all(u8::is_ascii_digit) {
+            let length: u64 = std::str::from_utf8(line)
+                .expect("ascii digits")
+                .parse()
+                .map_err(|err| std::io::Error::new(std::io::ErrorKind::InvalidData, err))?;
+
+            frame.clear();
+            (&mut stdin).take(length).read_to_end(&mut frame)?;
+            if (frame.len() as u64) < length {
+                return Err(std::io::ErrorKind::UnexpectedEof.into());
+            }
+        }
+
+        let response = framed_response(detector, &frame, position);
+        writeln!(stdout, "{}", response)?;
+        // callers are waiting on each result before they can carry on
+        stdout.flush()?;
+    }
+}
+
 // use stats_alloc::{Region, StatsAlloc, INSTRUMENTED_SYSTEM};
 
 // #[global_allocator]
@@ -96,84 +468,189 @@ async fn main() -> Result<(), ExitCode> {
     // let reg = stats_alloc::Region::new(&INSTRUMENTED_SYSTEM);
 
     let args = Args::parse();
-    #[cfg(not(feature = "cache"))]
-    let detector = DeviceDetector::new();
+
+    let mut builder = DeviceDetector::builder();
+
+    if let Some(dir) = &args.regexes {
+        eprintln!("Loading regexes from {}", dir.display());
+        builder = builder.regex_dir(dir);
+    }
 
     #[cfg(feature = "cache")]
-    let detector = if let Some(entries) = args.cache {
+    if let Some(entries) = args.cache {
         eprintln!("Cache enabled ({} entries)", entries);
-        DeviceDetector::new_with_cache(entries)
-    } else {
-        DeviceDetector::new()
-    };
+        builder = builder.cache(entries);
+    }
 
-    if args.interactive {
-        eprintln!("Starting interactive mode");
-        let mut ua = String::with_capacity(50); // may also use with_capacity if you can guess
-        while std::io::stdin().read_line(&mut ua).unwrap() > 0 {
-            let headers = None;
+    if let Some(dir) = &args.cache_dir {
+        builder = builder.disk_cache(dir);
+    }
 
-            let detection = detector
-                .parse(ua.trim_end(), headers)
-                .unwrap_or_else(|_| panic!("parse failed for {}", &ua));
+    builder = builder.options(DetectionOptions {
+        version_truncation: args.version_truncation,
+        skip_bot_detection: args.skip_bot_detection,
+        detect_bot_devices: args.detect_bot_devices,
+        discard_bot_information: args.discard_bot_information,
+    });
 
-            if args.gen_test_case {
-                println!("{}", detection.to_test_case(&ua));
-            } else {
-                // println!("user_agent: {}", &ua);
-                println!("{}", detection.to_value());
-            }
+    let detector = builder.clone().build().map_err(|err| {
+        eprintln!("Unable to set up the detector: {:#}", err);
+        ExitCode::FAILURE
+    })?;
 
-            ua.clear(); // clear to reuse the buffer
-        }
+    let warm_up_threads = args.warm.then(|| {
+        std::thread::available_parallelism()
+            .map(|threads| threads.get())
+            .unwrap_or(1)
+    });
+
+    if let Some(threads) = warm_up_threads {
+        let warm_up = detector.warm_up(threads).map_err(|err| {
+            eprintln!("Unable to compile regexes: {:#}", err);
+            ExitCode::FAILURE
+        })?;
+        eprintln!(
+            "Compiled {} of {} regexes in {:.1?}",
+            warm_up.compiled, warm_up.patterns, warm_up.elapsed
+        );
+    }
+
+    if args.interactive {
+        eprintln!("Starting interactive mode");
+        interactive(&detector, args.input_format, args.gen_test_case).map_err(|err| {
+            eprintln!("Unable to read stdin: {}", err);
+            ExitCode::FAILURE
+        })?;
+    } else if args.framed {
+        eprintln!("Starting framed mode");
+        framed(&detector).map_err(|err| {
+            eprintln!("Unable to read stdin: {}", err);
+            ExitCode::FAILURE
+        })?;
     } else if args.server {
         eprintln!("Starting server mode");
         let ip: IpAddr = args.ip.parse().expect("valid ip address (ipv4 or ipv6)");
-        let sock = SocketAddr::new(ip, args.port);
+        let listen = Listen::Tcp(SocketAddr::new(ip, args.port));
 
-        server(sock, detector).await;
-    } else {
-        match args.useragent {
-            None => {
-                eprintln!("No user agent specified");
-                return Ok(());
-            }
+        #[cfg(unix)]
+        let listen = match &args.unix_socket {
+            Some(path) => Listen::Unix(path.clone()),
+            None => listen,
+        };
+
+        let mut options = Options {
+            reload: None,
+            limits: Limits {
+                max_body_bytes: args.max_body_bytes,
+                request_timeout: Duration::from_secs(args.request_timeout),
+                max_concurrent_requests: args.max_concurrent_requests,
+                header_read_timeout: Duration::from_secs(args.header_read_timeout),
+            },
+            keep_alive: !args.no_keep_alive,
+            shutdown_timeout: Duration::from_secs(args.shutdown_timeout),
+        };
+
+        if args.regexes.is_some() {
+            let smoke_corpus = match &args.smoke_corpus {
+                Some(path) => std::fs::read_to_string(path)
+                    .map_err(|err| {
+                        eprintln!("Unable to read {}: {}", path.display(), err);
+                        ExitCode::FAILURE
+                    })?
+                    .lines()
+                    .filter(|line| !line.trim().is_empty())
+                    .map(str::to_owned)
+                    .collect(),
+                None => Vec::new(),
+            };
 
-            Some(ua) => {
-                // eprintln!("ua: {}", ua);
-                // eprintln!("headers: {:?}", args.headers);
-                let headers: Option<Vec<(String, String)>> = match args.headers {
-                    Some(headers) => {
-                        headers
-                            .into_iter()
-                            .map(|x| {
-                                let mut split = x.split(":");
-                                // TODO FIXME handle all of these panics more cleanly.
-                                let name = split
-                                    .next()
-                                    .expect(&format!("Unable to parse header {}", x));
-                                let val = split
-                                    .next()
-                                    .expect(&format!("Unable to parse header {}", x));
-                                Some((name.to_owned(), val.trim_start().to_owned()))
-                            })
-                            .collect()
+            let token = match &args.reload_token_file {
+                Some(path) => {
+                    let token = std::fs::read_to_string(path).map_err(|err| {
+                        eprintln!("Unable to read {}: {}", path.display(), err);
+                        ExitCode::FAILURE
+                    })?;
+                    let token = token.lines().next().unwrap_or_default().trim();
+                    if token.is_empty() {
+                        eprintln!("No reload token in {}", path.display());
+                        return Err(ExitCode::FAILURE);
                     }
+                    Some(token.to_owned())
+                }
+                None => None,
+            };
 
-                    None => None,
-                };
+            options.reload = Some(Reload {
+                builder,
+                watch_interval: args.watch.then(|| Duration::from_secs(args.watch_interval)),
+                smoke_corpus,
+                warm_up_threads,
+                token,
+            });
+        }
 
-                let detection = detector
-                    .parse(&ua, headers)
-                    .unwrap_or_else(|err| panic!("parse failed {} for '{}'", err, &ua));
+        server_with_options(listen, detector, options).await;
+    } else {
+        let mut headers: Option<Vec<(String, String)>> = None;
 
-                if args.gen_test_case {
-                    println!("{}", detection.to_test_case(&ua));
-                } else {
-                    println!("{}", detection.to_value());
+        if let Some(path) = &args.headers_file {
+            let block = if path.as_os_str() == "-" {
+                std::io::read_to_string(std::io::stdin())
+            } else {
+                std::fs::read_to_string(path)
+            };
+            let block = block.map_err(|err| {
+                eprintln!("Unable to read {}: {}", path.display(), err);
+                ExitCode::FAILURE
+            })?;
+
+            match parse_header_block(&block) {
+                Ok(block) => headers.get_or_insert_with(Vec::new).extend(block),
+                Err(err) => {
+                    println!("{}", err.to_value());
+                    return Err(ExitCode::FAILURE);
                 }
             }
         }
+
+        for header in args.header.iter().flatten() {
+            // values can have colons of their own
+            let Some((name, value)) = header.split_once(':') else {
+                eprintln!("Unable to parse header {}, expected `Name: value`", header);
+                return Err(ExitCode::FAILURE);
+            };
+            headers
+                .get_or_insert_with(Vec::new)
+                .push((name.trim().to_owned(), value.trim().to_owned()));
+        }
+
+        let ua = args.useragent.clone().or_else(|| {
+            headers
+                .iter()
+                .flatten()
+                .find(|(name, _)| name.eq_ignore_ascii_case("user-agent"))
+                .map(|(_, ua)| ua.clone())
+        });
+        let Some(ua) = ua else {
+            eprintln!("No user agent specified");
+            return Err(ExitCode::FAILURE);
+        };
+
+        // eprintln!("ua: {}", ua);
+        // eprintln!("headers: {:?}", headers);
+        let detection = match detector.parse(&ua, headers) {
+            Ok(detection) => detection,
+            Err(err) => {
+                println!("{}", err.to_value());
+                return Err(ExitCode::FAILURE);
+            }
+        };
+
+        if args.gen_test_case {
+            println!("{}", detection.to_test_case(&ua));
+        } else {
+            println!("{}", detection.to_value());
+        }
     }
 
     // let ch = reg.change();
@@ -0,0 +1,135 @@
+use std::fmt;
+use std::str::FromStr;
+
+use crate::device_detector::{DetectionRef, KnownDeviceRef};
+
+/// Changes to how a [`DeviceDetector`] detects and reports user agents. Apart
+/// from `skip_bot_detection` and `detect_bot_devices`, which are part of the
+/// cache key, these are applied to each detection after it is made, or taken
+/// from the cache, so detectors with different options can share a cache.
+///
+/// [`DeviceDetector`]: crate::device_detector::DeviceDetector
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct DetectionOptions {
+    /// How much of the client, engine and os versions to keep.
+    pub version_truncation: VersionTruncation,
+
+    /// Like upstream's `skipBotDetection`, don't look for bots at all, and
+    /// detect the client, device and os of every user agent, bots included.
+    pub skip_bot_detection: bool,
+
+    /// Detect the client, device and os that bots claim to be as well, and
+    /// report them as a [`Detection::Known`] with [`KnownDevice::bot`] set,
+    /// rather than as a [`Detection::Bot`]. Crawlers such as Googlebot's
+    /// smartphone one send the user agent of a whole phone, which is
+    /// otherwise lost. Does nothing with `skip_bot_detection`.
+    ///
+    /// [`Detection::Known`]: crate::device_detector::Detection::Known
+    /// [`Detection::Bot`]: crate::device_detector::Detection::Bot
+    pub detect_bot_devices: bool,
+
+    /// Like upstream's `discardBotInformation`, report bots as
+    /// [`Detection::AnonymousBot`], without saying which bot it is. That
+    /// goes for bots found with `detect_bot_devices` too, whose device is then
+    /// dropped along with the bot, as upstream never reports a bot's device.
+    ///
+    /// [`Detection::AnonymousBot`]: crate::device_detector::Detection::AnonymousBot
+    pub discard_bot_information: bool,
+}
+
+impl DetectionOptions {
+    pub(crate) fn apply(&self, detection: &mut DetectionRef<'_>) {
+        match detection {
+            DetectionRef::Known(known) if known.bot.is_some() && self.discard_bot_information => {
+                *detection = DetectionRef::AnonymousBot;
+            }
+            DetectionRef::Known(known) => self.apply_known(known),
+            DetectionRef::Bot(_) if self.discard_bot_information => {
+                *detection = DetectionRef::AnonymousBot;
+            }
+            _ => {}
+        }
+    }
+
+    fn apply_known(&self, known: &mut KnownDeviceRef<'_>) {
+        let truncation = self.version_truncation;
+
+        if let Some(client) = &mut known.client {
+            truncation.truncate(&mut client.version);
+            truncation.truncate(&mut client.engine_version);
+        }
+
+        if let Some(os) = &mut known.os {
+            truncation.truncate(&mut os.version);
+        }
+    }
+}
+
+/// Like upstream's `VERSION_TRUNCATION_*`, how many parts of a version to
+/// keep, so `1.2.3.4` becomes `1` with `Major`, `1.2` with `Minor` and so on.
+/// Versions with fewer parts than that are left as they are.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum VersionTruncation {
+    #[default]
+    None,
+    Major,
+    Minor,
+    Patch,
+    Build,
+}
+
+impl VersionTruncation {
+    fn parts(self) -> Option<usize> {
+        match self {
+            Self::None => None,
+            Self::Major => Some(1),
+            Self::Minor => Some(2),
+            Self::Patch => Some(3),
+            Self::Build => Some(4),
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::None => "none",
+            Self::Major => "major",
+            Self::Minor => "minor",
+            Self::Patch => "patch",
+            Self::Build => "build",
+        }
+    }
+
+    pub fn truncate(self, version: &mut Option<String>) {
+        let (Some(parts), Some(version)) = (self.parts(), version) else {
+            return;
+        };
+
+        if let Some((end, _)) = version.match_indices('.').nth(parts - 1) {
+            version.truncate(end);
+        }
+    }
+}
+
+impl fmt::Display for VersionTruncation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for VersionTruncation {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "none" => Ok(Self::None),
+            "major" => Ok(Self::Major),
+            "minor" => Ok(Self::Minor),
+            "patch" => Ok(Self::Patch),
+            "build" => Ok(Self::Build),
+            _ => Err(format!(
+                "unknown version truncation {}, expected one of none, major, minor, patch or build",
+                s
+            )),
+        }
+    }
+}
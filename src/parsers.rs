@@ -1,6 +1,8 @@
 pub(crate) mod bot;
 pub(crate) mod client;
+pub(crate) mod database;
 pub(crate) mod device;
 pub(crate) mod oss;
+pub(crate) mod prefilter;
 pub(crate) mod utils;
 pub(crate) mod vendor_fragments;
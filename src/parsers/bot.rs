@@ -1,22 +1,20 @@
 use anyhow::Result;
 
-use serde::Deserialize;
+use serde::{Deserialize, Deserializer};
 
-use once_cell::sync::Lazy;
 use serde::Serialize;
 
-use crate::parsers::utils::{lazy_user_agent_match, LazyRegex};
+use std::borrow::Cow;
 
-static BOT_LIST: Lazy<BotList> = Lazy::new(|| {
-    let contents = include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/regexes/bots.yml"));
-    BotList::from_file(contents).unwrap_or_else(|_| panic!("loading bots.yml"))
-});
+use crate::parsers::database::RegexDatabase;
+use crate::parsers::prefilter::Prefilter;
+use crate::parsers::utils::{lazy_user_agent_match, LazyRegex};
 
-pub fn lookup_bot(ua: &str) -> Result<Option<Bot>> {
-    BOT_LIST.lookup(ua)
+pub fn lookup_bot<'a>(db: &'a RegexDatabase, ua: &str) -> Result<Option<BotRef<'a>>> {
+    Ok(db.bots.lookup(ua)?.map(BotRef::from))
 }
 
-#[derive(Clone, Debug, Serialize)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Bot {
     pub name: String,
     pub category: Option<String>,
@@ -24,6 +22,45 @@ pub struct Bot {
     pub producer: Option<BotProducer>,
 }
 
+/// A [`Bot`] that borrows from the regexes it was found with instead of
+/// copying out of them, which is where all of a bot's fields come from. One
+/// taken from a cache owns its fields instead.
+#[derive(Clone, Debug, Serialize)]
+pub struct BotRef<'a> {
+    pub name: Cow<'a, str>,
+    pub category: Option<Cow<'a, str>>,
+    pub url: Option<Cow<'a, str>>,
+    pub producer: Option<Cow<'a, BotProducer>>,
+}
+
+impl BotRef<'_> {
+    pub fn to_owned(&self) -> Bot {
+        self.clone().into()
+    }
+}
+
+impl From<BotRef<'_>> for Bot {
+    fn from(bot: BotRef<'_>) -> Self {
+        Bot {
+            name: bot.name.into_owned(),
+            category: bot.category.map(Cow::into_owned),
+            url: bot.url.map(Cow::into_owned),
+            producer: bot.producer.map(Cow::into_owned),
+        }
+    }
+}
+
+impl From<Bot> for BotRef<'_> {
+    fn from(bot: Bot) -> Self {
+        BotRef {
+            name: Cow::Owned(bot.name),
+            category: bot.category.map(Cow::Owned),
+            url: bot.url.map(Cow::Owned),
+            producer: bot.producer.map(Cow::Owned),
+        }
+    }
+}
+
 #[derive(Debug, Deserialize, Clone, Serialize)]
 pub struct BotProducer {
     pub name: Option<String>,
@@ -39,13 +76,13 @@ struct BotEntry {
     producer: Option<BotProducer>,
 }
 
-impl From<&BotEntry> for Bot {
-    fn from(entry: &BotEntry) -> Self {
-        Bot {
-            name: entry.name.clone(),
-            category: entry.category.clone(),
-            url: entry.url.clone(),
-            producer: entry.producer.clone(),
+impl<'a> From<&'a BotEntry> for BotRef<'a> {
+    fn from(entry: &'a BotEntry) -> Self {
+        BotRef {
+            name: Cow::Borrowed(&entry.name),
+            category: entry.category.as_deref().map(Cow::Borrowed),
+            url: entry.url.as_deref().map(Cow::Borrowed),
+            producer: entry.producer.as_ref().map(Cow::Borrowed),
         }
     }
 }
@@ -53,10 +90,19 @@ impl From<&BotEntry> for Bot {
 #[derive(Debug)]
 pub struct BotList {
     bots: Vec<BotEntry>,
+    prefilter: Prefilter,
 }
 
 impl BotList {
     pub fn from_file(contents: &str) -> Result<BotList> {
+        Self::from_deserializer(serde_yaml::Deserializer::from_str(contents))
+    }
+
+    pub(crate) fn from_deserializer<'de, D>(deserializer: D) -> Result<BotList>
+    where
+        D: Deserializer<'de>,
+        D::Error: Send + Sync + 'static,
+    {
         #[derive(Debug, Deserialize)]
         struct YamlBotEntry {
             regex: String,
@@ -99,18 +145,33 @@ impl BotList {
             fn into(self) -> BotList {
                 BotList {
                     bots: self.bots.into_iter().map(|x| x.into()).collect(),
+                    prefilter: Prefilter::default(),
                 }
             }
         }
 
-        let res: YamlBotList = serde_yaml::from_str(contents)?;
+        let res = YamlBotList::deserialize(deserializer)?;
         Ok(res.into())
     }
 
-    fn lookup(&self, ua: &str) -> Result<Option<Bot>> {
-        for bot in self.bots.iter() {
+    pub(crate) fn regexes(&self) -> impl Iterator<Item = &LazyRegex> {
+        self.bots.iter().map(|bot| &bot.regex)
+    }
+
+    fn patterns(&self) -> impl Iterator<Item = &str> {
+        self.bots.iter().map(|bot| bot.regex.pattern.as_str())
+    }
+
+    pub(crate) fn build_prefilter(&self) {
+        self.prefilter.build(|| self.patterns());
+    }
+
+    fn lookup(&self, ua: &str) -> Result<Option<&BotEntry>> {
+        let candidates = self.prefilter.candidates(ua, || self.patterns());
+
+        for bot in candidates.into_iter().map(|i| &self.bots[i]) {
             if bot.regex.is_match(ua)? {
-                return Ok(Some(bot.into()));
+                return Ok(Some(bot));
             }
         }
 
@@ -23,7 +23,10 @@ use serde::{Deserialize, Serialize};
 
 use serde::de::Deserializer;
 
+use std::borrow::Cow;
+
 use crate::known_browsers::AvailableBrowser;
+use crate::parsers::database::RegexDatabase;
 use crate::parsers::utils::{lazy_user_agent_match, LazyRegex};
 
 pub mod browsers;
@@ -37,7 +40,7 @@ pub mod pim;
 use crate::client_hints::ClientHint;
 
 #[repr(C)]
-#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub enum ClientType {
     #[serde(rename = "browser")]
     Browser,
@@ -69,6 +72,9 @@ impl ClientType {
 #[derive(Clone, Debug, Serialize)]
 pub struct Client {
     pub name: String,
+    /// Matomo's code for the browser, such as `CH` for Chrome. Only browsers
+    /// have one.
+    pub short_name: Option<String>,
     pub version: Option<String>,
     pub r#type: ClientType,
     pub engine: Option<String>,
@@ -78,28 +84,100 @@ pub struct Client {
     pub(crate) browser: Option<AvailableBrowser>,
 }
 
-pub fn lookup(ua: &str, client_hints: Option<&ClientHint>) -> Result<Option<Client>> {
-    if let Some(res) = feed_readers::lookup(ua)? {
+impl Client {
+    pub(crate) fn uses_mobile_browser(&self) -> bool {
+        self.r#type == ClientType::Browser
+            && self
+                .browser
+                .as_ref()
+                .is_some_and(|browser| browser.mobile_only)
+    }
+}
+
+/// A [`Client`] that borrows its name and engine from the regexes and known
+/// browsers it was found with, rather than copying them. Versions come out
+/// of the user agent itself, so those are always owned.
+#[derive(Clone, Debug, Serialize)]
+pub struct ClientRef<'a> {
+    pub name: Cow<'a, str>,
+    pub short_name: Option<Cow<'a, str>>,
+    pub version: Option<String>,
+    pub r#type: ClientType,
+    pub engine: Option<Cow<'a, str>>,
+    pub engine_version: Option<String>,
+
+    #[serde(skip)]
+    pub(crate) browser: Option<Cow<'a, AvailableBrowser>>,
+}
+
+impl ClientRef<'_> {
+    pub fn to_owned(&self) -> Client {
+        self.clone().into()
+    }
+
+    pub(crate) fn uses_mobile_browser(&self) -> bool {
+        self.r#type == ClientType::Browser
+            && self
+                .browser
+                .as_ref()
+                .is_some_and(|browser| browser.mobile_only)
+    }
+}
+
+impl From<ClientRef<'_>> for Client {
+    fn from(client: ClientRef<'_>) -> Self {
+        Client {
+            name: client.name.into_owned(),
+            short_name: client.short_name.map(Cow::into_owned),
+            version: client.version,
+            r#type: client.r#type,
+            engine: client.engine.map(Cow::into_owned),
+            engine_version: client.engine_version,
+            browser: client.browser.map(Cow::into_owned),
+        }
+    }
+}
+
+impl From<Client> for ClientRef<'_> {
+    fn from(client: Client) -> Self {
+        ClientRef {
+            name: Cow::Owned(client.name),
+            short_name: client.short_name.map(Cow::Owned),
+            version: client.version,
+            r#type: client.r#type,
+            engine: client.engine.map(Cow::Owned),
+            engine_version: client.engine_version,
+            browser: client.browser.map(Cow::Owned),
+        }
+    }
+}
+
+pub fn lookup<'a>(
+    db: &'a RegexDatabase,
+    ua: &str,
+    client_hints: Option<&ClientHint>,
+) -> Result<Option<ClientRef<'a>>> {
+    if let Some(res) = feed_readers::lookup(db, ua)? {
         return Ok(Some(res));
     }
 
-    if let Some(res) = mobile_apps::lookup(ua, client_hints)? {
+    if let Some(res) = mobile_apps::lookup(db, ua, client_hints)? {
         return Ok(Some(res));
     }
 
-    if let Some(res) = media_players::lookup(ua)? {
+    if let Some(res) = media_players::lookup(db, ua)? {
         return Ok(Some(res));
     }
 
-    if let Some(res) = pim::lookup(ua)? {
+    if let Some(res) = pim::lookup(db, ua)? {
         return Ok(Some(res));
     }
 
-    if let Some(res) = browsers::lookup(ua, client_hints)? {
+    if let Some(res) = browsers::lookup(db, ua, client_hints)? {
         return Ok(Some(res));
     }
 
-    if let Some(res) = libraries::lookup(ua)? {
+    if let Some(res) = libraries::lookup(db, ua)? {
         return Ok(Some(res));
     }
 
@@ -111,6 +189,7 @@ pub struct ClientEntry {
     name: String,
     #[serde(deserialize_with = "de_regex")]
     regex: LazyRegex,
+    #[serde(deserialize_with = "de_version")]
     version: String,
 }
 
@@ -120,19 +199,22 @@ pub struct ClientList {
 }
 
 impl ClientList {
-    pub fn lookup(&self, ua: &str, r#type: ClientType) -> Result<Option<Client>> {
+    pub(crate) fn regexes(&self) -> impl Iterator<Item = &LazyRegex> {
+        self.clients.iter().map(|client| &client.regex)
+    }
+
+    pub fn lookup(&self, ua: &str, r#type: ClientType) -> Result<Option<ClientRef<'_>>> {
         for client in self.clients.iter() {
             if client.regex.is_match(ua)? {
                 let mut version = "".to_owned();
-                let mut name = "".to_owned();
                 let caps = client.regex.captures(ua)?.expect("valid_regex");
 
                 // expands $1, $2 etc in names / versions to captures from regex
                 caps.expand(&client.version, &mut version);
 
                 // TODO I don't know if this is needed, but here it is.
-                let version = if version.ends_with(&['.', ' ']) {
-                    version.trim_end_matches(&['.', ' ']).to_owned()
+                let version = if version.ends_with(['.', ' ']) {
+                    version.trim_end_matches(['.', ' ']).to_owned()
                 } else {
                     version
                 };
@@ -143,10 +225,11 @@ impl ClientList {
                     None
                 };
 
-                caps.expand(&client.name, &mut name);
+                let name = caps.expand_cow(&client.name);
 
-                return Ok(Some(Client {
+                return Ok(Some(ClientRef {
                     name,
+                    short_name: None,
                     version,
                     r#type,
                     browser: None,
@@ -160,6 +243,14 @@ impl ClientList {
     }
 
     pub fn from_file(contents: &str) -> Result<ClientList> {
+        Self::from_deserializer(serde_yaml::Deserializer::from_str(contents))
+    }
+
+    pub(crate) fn from_deserializer<'de, D>(deserializer: D) -> Result<ClientList>
+    where
+        D: Deserializer<'de>,
+        D::Error: Send + Sync + 'static,
+    {
         #[derive(Debug, Deserialize)]
         #[serde(transparent)]
         pub struct YamlClientList {
@@ -175,7 +266,7 @@ impl ClientList {
             }
         }
 
-        let res: YamlClientList = serde_yaml::from_str(contents)?;
+        let res = YamlClientList::deserialize(deserializer)?;
         Ok(res.into())
     }
 }
@@ -201,3 +292,13 @@ where
     let s = String::deserialize(deserializer)?;
     Ok(lazy_user_agent_match(&s))
 }
+
+// A few entries have an empty `version:`, which yaml reads as an empty string
+// but anything that went through a `serde_yaml::Value` sees as null.
+fn de_version<'de, D>(deserializer: D) -> Result<String, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let s = Option::<String>::deserialize(deserializer)?;
+    Ok(s.unwrap_or_default())
+}
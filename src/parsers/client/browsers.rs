@@ -2,34 +2,30 @@ use anyhow::Result;
 
 use fancy_regex::Regex;
 
-use serde::Deserialize;
+use serde::{Deserialize, Deserializer};
 
 use version_compare::Cmp;
 
+use std::borrow::Cow;
 use std::cmp::Ordering;
 
-use std::collections::HashMap;
+use indexmap::IndexMap;
 
 use fallible_iterator::{convert, FallibleIterator};
 
-use super::{Client, ClientType};
+use super::{ClientRef, ClientType};
 use crate::client_hints::{ClientHint, ClientHintMapping};
 use crate::known_browsers::AvailableBrowsers;
 
+use crate::parsers::database::RegexDatabase;
 use crate::parsers::utils::LazyRegex;
 
+use self::engines::BrowserEngineList;
+
 pub mod engines;
 
 use once_cell::sync::Lazy;
 
-static CLIENT_LIST: Lazy<BrowserClientList> = Lazy::new(|| {
-    let contents = include_str!(concat!(
-        env!("CARGO_MANIFEST_DIR"),
-        "/regexes/client/browsers.yml"
-    ));
-    BrowserClientList::from_file(contents).expect("loading browsers.yml")
-});
-
 static CLIENT_HINT_MAPPING: Lazy<ClientHintMapping> = Lazy::new(|| {
     ClientHintMapping::new(vec![
         ("Chrome".to_owned(), vec!["Google Chrome".to_owned()]),
@@ -56,8 +52,12 @@ static CLIENT_HINT_MAPPING: Lazy<ClientHintMapping> = Lazy::new(|| {
 
 static AVAILABLE_BROWSERS: Lazy<AvailableBrowsers> = Lazy::new(AvailableBrowsers::default);
 
-pub fn lookup(ua: &str, client_hints: Option<&ClientHint>) -> Result<Option<Client>> {
-    let client_from_ua: Option<Client> = CLIENT_LIST.lookup(ua)?;
+pub fn lookup<'a>(
+    db: &'a RegexDatabase,
+    ua: &str,
+    client_hints: Option<&ClientHint>,
+) -> Result<Option<ClientRef<'a>>> {
+    let client_from_ua: Option<ClientRef<'a>> = db.browsers.lookup(&db.browser_engines, ua)?;
 
     let mut client_from_hints = if let Some(client_hints) = client_hints {
         let client_hints_iter = convert(client_hints.full_version_list.iter().map(anyhow::Ok));
@@ -82,13 +82,14 @@ pub fn lookup(ua: &str, client_hints: Option<&ClientHint>) -> Result<Option<Clie
                 Some(brand_version.to_owned())
             };
 
-            let res = Client {
-                name: brand_result.name.clone(),
+            let res = ClientRef {
+                name: Cow::Borrowed(&brand_result.name),
+                short_name: None,
                 version,
                 r#type: ClientType::Browser,
                 engine: None,
                 engine_version: None,
-                browser: Some(brand_result.to_owned()),
+                browser: Some(Cow::Borrowed(brand_result)),
             };
             Some(res)
         } else {
@@ -106,7 +107,7 @@ pub fn lookup(ua: &str, client_hints: Option<&ClientHint>) -> Result<Option<Clie
                 .iter()
                 .any(|year| client_hints_version.starts_with(year));
             if iridium {
-                client_from_hints.name = "Iridium".to_owned();
+                client_from_hints.name = "Iridium".into();
             }
 
             // https://bbs.360.cn/thread-16096544-1-1.html
@@ -115,7 +116,7 @@ pub fn lookup(ua: &str, client_hints: Option<&ClientHint>) -> Result<Option<Clie
                     if client_hints_version.starts_with("15")
                         && ua_client_version.starts_with("114")
                     {
-                        client_from_hints.name = "360 Secure Browser".to_owned();
+                        client_from_hints.name = "360 Secure Browser".into();
                         client_from_hints.engine = ua_client.engine.clone();
                         client_from_hints.engine_version = ua_client.engine_version.clone();
                     }
@@ -155,7 +156,7 @@ pub fn lookup(ua: &str, client_hints: Option<&ClientHint>) -> Result<Option<Clie
         }
 
         if let Some(client) = &client_from_ua {
-            if client.name == format!("{} Mobile", client_from_hints.name) {
+            if client.name.strip_suffix(" Mobile") == Some(&*client_from_hints.name) {
                 client_from_hints.name = client_from_ua
                     .as_ref()
                     .map(|x| x.name.clone())
@@ -207,9 +208,9 @@ pub fn lookup(ua: &str, client_hints: Option<&ClientHint>) -> Result<Option<Clie
     if let Some(client) = res.as_mut() {
         if let Some(client_hints) = client_hints {
             if let Some(app_hint) = &client_hints.app {
-                if let Some(app_name) = super::hints::browsers::get_hint(app_hint)? {
+                if let Some(app_name) = super::hints::browsers::get_hint(db, app_hint)? {
                     if client.name != app_name {
-                        client.name = app_name.to_owned();
+                        client.name = app_name.into();
                         client.version = None;
 
                         if let Some(browser) = AVAILABLE_BROWSERS.search_by_name(app_name) {
@@ -218,16 +219,16 @@ pub fn lookup(ua: &str, client_hints: Option<&ClientHint>) -> Result<Option<Clie
                             });
 
                             if BLINK_REGEX.is_match(ua)? {
-                                client.engine = Some("Blink".to_owned());
+                                client.engine = Some("Blink".into());
 
                                 if let Some(engine) = &client.engine {
                                     client.engine_version =
                                         BrowserClientList::engine_version(ua, engine)?;
                                 }
 
-                                let mut client_browser = browser.clone();
+                                let mut client_browser = Cow::Borrowed(browser);
                                 if client_browser.family.is_none() {
-                                    client_browser.family = Some("Chrome".to_owned());
+                                    client_browser.to_mut().family = Some("Chrome".to_owned());
                                 }
 
                                 client.browser = Some(client_browser);
@@ -246,10 +247,15 @@ pub fn lookup(ua: &str, client_hints: Option<&ClientHint>) -> Result<Option<Clie
             }
 
             if client.name == "Every Browser" {
-                client.engine = Some("Blink".to_owned());
+                client.engine = Some("Blink".into());
                 client.engine_version = None;
             }
         }
+
+        // only now that the name is settled
+        client.short_name = AVAILABLE_BROWSERS
+            .search_by_name(&client.name)
+            .map(|browser| Cow::Borrowed(browser.short_name.as_str()));
     }
 
     Ok(res)
@@ -268,45 +274,52 @@ struct BrowserClientEntry {
 struct BrowserEngine {
     default: Option<String>,
     #[serde(default)]
-    versions: HashMap<String, String>,
+    versions: IndexMap<String, String>,
 }
 
 #[derive(Debug, Default, Deserialize)]
 #[serde(transparent)]
-struct BrowserClientList {
+pub(crate) struct BrowserClientList {
     clients: Vec<BrowserClientEntry>,
 }
 
 impl BrowserClientList {
-    pub fn lookup(&self, ua: &str) -> Result<Option<Client>> {
+    pub(crate) fn regexes(&self) -> impl Iterator<Item = &LazyRegex> {
+        self.clients.iter().map(|client| &client.regex)
+    }
+
+    pub fn lookup<'a>(
+        &'a self,
+        engines: &'a BrowserEngineList,
+        ua: &str,
+    ) -> Result<Option<ClientRef<'a>>> {
         for entry in self.clients.iter() {
             if entry.regex.is_match(ua)? {
-                let mut name = "".to_owned();
                 let mut version = "".to_owned();
 
                 let caps = entry.regex.captures(ua)?.expect("valid_regex");
 
                 caps.expand(&entry.version, &mut version);
-                let version = if version.ends_with(&['.', ' ']) {
-                    version.trim_end_matches(&['.', ' ']).to_owned()
+                let version = if version.ends_with(['.', ' ']) {
+                    version.trim_end_matches(['.', ' ']).to_owned()
                 } else {
                     version
                 };
 
-                caps.expand(&entry.name, &mut name);
+                let name = caps.expand_cow(&entry.name);
 
                 // browsers are always have engine versions even if they are empty strings
                 let mut engine = None;
                 let mut engine_version = None;
 
                 if let Some(entry_engine) = &entry.engine {
-                    if let Some(e) = Self::engine(ua, entry_engine, &version)? {
+                    if let Some(e) = Self::engine(engines, ua, entry_engine, &version)? {
                         engine = Some(e);
                     }
                 }
 
                 if engine.is_none() {
-                    engine = self::engines::lookup(ua)?;
+                    engine = self::engines::lookup(engines, ua)?;
                 }
 
                 if let Some(e) = &engine {
@@ -315,9 +328,7 @@ impl BrowserClientList {
                     }
                 }
 
-                let browser = AVAILABLE_BROWSERS
-                    .search_by_name(&name)
-                    .map(|browser| browser.to_owned());
+                let browser = AVAILABLE_BROWSERS.search_by_name(&name).map(Cow::Borrowed);
 
                 let version = if version.is_empty() {
                     None
@@ -325,11 +336,12 @@ impl BrowserClientList {
                     Some(version)
                 };
 
-                return Ok(Some(Client {
+                return Ok(Some(ClientRef {
                     name,
+                    short_name: None,
                     version,
                     r#type: ClientType::Browser,
-                    engine,
+                    engine: engine.map(Cow::Borrowed),
                     engine_version,
                     browser,
                 }));
@@ -386,7 +398,12 @@ impl BrowserClientList {
         Ok(None)
     }
 
-    fn engine(ua: &str, entry_engine: &BrowserEngine, version: &str) -> Result<Option<String>> {
+    fn engine<'a>(
+        engines: &'a BrowserEngineList,
+        ua: &str,
+        entry_engine: &'a BrowserEngine,
+        version: &str,
+    ) -> Result<Option<&'a str>> {
         let mut engine = None;
         let mut engine_versions = entry_engine.versions.iter().collect::<Vec<_>>();
 
@@ -407,14 +424,14 @@ impl BrowserClientList {
                 if let Cmp::Eq | Cmp::Gt =
                     version_compare::compare(version, engine_version).expect("valid version")
                 {
-                    engine = Some(eng.clone())
+                    engine = Some(eng.as_str())
                 }
             });
 
-        engine = engine.or_else(|| entry_engine.default.clone());
+        engine = engine.or(entry_engine.default.as_deref());
 
-        if engine.is_none() || engine.as_ref().unwrap() == "" {
-            engine = self::engines::lookup(ua)?;
+        if let None | Some("") = engine {
+            engine = self::engines::lookup(engines, ua)?;
         }
 
         Ok(engine)
@@ -424,4 +441,13 @@ impl BrowserClientList {
         let res = serde_yaml::from_str(contents)?;
         Ok(res)
     }
+
+    pub(crate) fn from_deserializer<'de, D>(deserializer: D) -> Result<Self>
+    where
+        D: Deserializer<'de>,
+        D::Error: Send + Sync + 'static,
+    {
+        let res = Self::deserialize(deserializer)?;
+        Ok(res)
+    }
 }
@@ -1,17 +1,9 @@
 use anyhow::Result;
-use serde::Deserialize;
+use serde::{Deserialize, Deserializer};
 
 use crate::parsers::utils::{lazy_user_agent_match, LazyRegex};
 use once_cell::sync::Lazy;
 
-static ENGINE_LIST: Lazy<BrowserEngineList> = Lazy::new(|| {
-    let contents = include_str!(concat!(
-        env!("CARGO_MANIFEST_DIR"),
-        "/regexes/client/browser_engine.yml"
-    ));
-    BrowserEngineList::from_file(contents).expect("loading browser_engine.yml")
-});
-
 static AVAILABLE_ENGINES: Lazy<Vec<String>> = Lazy::new(|| {
     // hard coded list taken from matamoto device detector
     let engines = [
@@ -38,19 +30,20 @@ static AVAILABLE_ENGINES: Lazy<Vec<String>> = Lazy::new(|| {
     engines.into_iter().map(|x| x.to_owned()).collect()
 });
 
-pub fn lookup(name: &str) -> Result<Option<String>> {
+pub fn lookup<'a>(engines: &'a BrowserEngineList, name: &str) -> Result<Option<&'a str>> {
     // println!("browser engine lookup {}", name);
-    let res = match ENGINE_LIST.lookup(name)? {
+    let res = match engines.lookup(name)? {
         None => AVAILABLE_ENGINES
             .iter()
             .find(|engine| engine.to_lowercase() == name.to_lowercase())
-            .map(|x| x.to_owned()),
+            .map(|x| x.as_str()),
         res => res,
     };
     Ok(res)
 }
 
-struct BrowserEngineList {
+#[derive(Debug)]
+pub(crate) struct BrowserEngineList {
     list: Vec<BrowserEngine>,
 }
 
@@ -61,18 +54,30 @@ struct BrowserEngine {
 }
 
 impl BrowserEngineList {
-    fn lookup(&self, ua: &str) -> Result<Option<String>> {
+    pub(crate) fn regexes(&self) -> impl Iterator<Item = &LazyRegex> {
+        self.list.iter().map(|engine| &engine.regex)
+    }
+
+    fn lookup(&self, ua: &str) -> Result<Option<&str>> {
         for engine in &self.list {
             // println!("engine {:?}", engine);
             if engine.regex.is_match(ua)? {
                 // println!("engine match {:?}", engine);
-                return Ok(Some(engine.name.clone()));
+                return Ok(Some(&engine.name));
             }
         }
 
         Ok(None)
     }
-    fn from_file(contents: &str) -> Result<Self> {
+    pub(crate) fn from_file(contents: &str) -> Result<Self> {
+        Self::from_deserializer(serde_yaml::Deserializer::from_str(contents))
+    }
+
+    pub(crate) fn from_deserializer<'de, D>(deserializer: D) -> Result<Self>
+    where
+        D: Deserializer<'de>,
+        D::Error: Send + Sync + 'static,
+    {
         #[derive(Debug, Deserialize)]
         #[serde(transparent)]
         struct YamlBrowserEngineList {
@@ -105,7 +110,7 @@ impl BrowserEngineList {
             }
         }
 
-        let res: YamlBrowserEngineList = serde_yaml::from_str(contents)?;
+        let res = YamlBrowserEngineList::deserialize(deserializer)?;
         Ok(res.into())
     }
 }
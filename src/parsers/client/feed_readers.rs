@@ -1,16 +1,8 @@
 use anyhow::Result;
 
-use super::{Client, ClientList};
-use once_cell::sync::Lazy;
+use super::ClientRef;
+use crate::parsers::database::RegexDatabase;
 
-static CLIENT_LIST: Lazy<ClientList> = Lazy::new(|| {
-    let contents = include_str!(concat!(
-        env!("CARGO_MANIFEST_DIR"),
-        "/regexes/client/feed_readers.yml"
-    ));
-    ClientList::from_file(contents).expect("loading feed_readers.yml")
-});
-
-pub fn lookup(ua: &str) -> Result<Option<Client>> {
-    CLIENT_LIST.lookup(ua, super::ClientType::FeedReader)
+pub fn lookup<'a>(db: &'a RegexDatabase, ua: &str) -> Result<Option<ClientRef<'a>>> {
+    db.feed_readers.lookup(ua, super::ClientType::FeedReader)
 }
@@ -1,6 +1,6 @@
 use anyhow::Result;
-use serde::Deserialize;
-use std::collections::HashMap;
+use indexmap::IndexMap;
+use serde::{Deserialize, Deserializer};
 
 pub mod apps;
 pub mod browsers;
@@ -8,7 +8,7 @@ pub mod browsers;
 #[derive(Debug, Deserialize)]
 pub struct HintList {
     #[serde(flatten)]
-    pub hints: HashMap<String, String>,
+    pub hints: IndexMap<String, String>,
 }
 
 impl HintList {
@@ -17,6 +17,15 @@ impl HintList {
         Ok(hints)
     }
 
+    pub(crate) fn from_deserializer<'de, D>(deserializer: D) -> Result<HintList>
+    where
+        D: Deserializer<'de>,
+        D::Error: Send + Sync + 'static,
+    {
+        let hints = HintList::deserialize(deserializer)?;
+        Ok(hints)
+    }
+
     pub fn get_hint(&self, app: &str) -> Result<Option<&str>> {
         let res = self.hints.get(app);
         Ok(res.map(|s| s.as_ref()))
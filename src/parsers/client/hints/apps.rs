@@ -1,16 +1,7 @@
 use anyhow::Result;
 
-use super::HintList;
-use once_cell::sync::Lazy;
+use crate::parsers::database::RegexDatabase;
 
-static HINT_LIST: Lazy<HintList> = Lazy::new(|| {
-    let contents = include_str!(concat!(
-        env!("CARGO_MANIFEST_DIR"),
-        "/regexes/client/hints/apps.yml"
-    ));
-    HintList::from_file(contents).expect("loading hints/apps.yml")
-});
-
-pub fn get_hint(app: &str) -> Result<Option<&str>> {
-    HINT_LIST.get_hint(app)
+pub fn get_hint<'a>(db: &'a RegexDatabase, app: &str) -> Result<Option<&'a str>> {
+    db.app_hints.get_hint(app)
 }
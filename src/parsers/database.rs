@@ -0,0 +1,277 @@
+use anyhow::{Context, Result};
+
+use once_cell::sync::Lazy;
+
+use std::borrow::Cow;
+use std::cell::Cell;
+use std::path::Path;
+use std::sync::Arc;
+
+use super::bot::BotList;
+use super::client::browsers::engines::BrowserEngineList;
+use super::client::browsers::BrowserClientList;
+use super::client::hints::HintList;
+use super::client::ClientList;
+use super::device::DeviceList;
+use super::oss::OSList;
+use super::utils::LazyRegex;
+use super::vendor_fragments::VendorFragmentList;
+
+// build.rs converts every yaml file into messagepack, which is much faster to
+// load, and hashes the yaml it came from.
+macro_rules! embedded {
+    ($path:literal) => {
+        (
+            $path,
+            include_bytes!(concat!(env!("OUT_DIR"), "/regexes/", $path, ".msgpack")).as_slice(),
+            u64::from_le_bytes(*include_bytes!(concat!(
+                env!("OUT_DIR"),
+                "/regexes/",
+                $path,
+                ".hash"
+            ))),
+        )
+    };
+}
+
+/// The contents of one file of the database, in either format.
+enum Source<'a> {
+    Yaml(Cow<'a, str>),
+    /// Along with the hash of the yaml it was compiled from.
+    MessagePack(&'a [u8], u64),
+}
+
+/// Hashes the contents of a yaml file. Must match `fnv1a` in build.rs.
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf29ce484222325, |hash, byte| {
+        (hash ^ *byte as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
+// Paths are relative to the root of the `regexes/` directory, which has the
+// same layout as upstream's.
+static EMBEDDED_FILES: [(&str, &[u8], u64); 20] = [
+    embedded!("bots.yml"),
+    embedded!("oss.yml"),
+    embedded!("vendorfragments.yml"),
+    embedded!("client/browsers.yml"),
+    embedded!("client/browser_engine.yml"),
+    embedded!("client/feed_readers.yml"),
+    embedded!("client/libraries.yml"),
+    embedded!("client/mediaplayers.yml"),
+    embedded!("client/mobile_apps.yml"),
+    embedded!("client/pim.yml"),
+    embedded!("client/hints/apps.yml"),
+    embedded!("client/hints/browsers.yml"),
+    embedded!("device/cameras.yml"),
+    embedded!("device/car_browsers.yml"),
+    embedded!("device/consoles.yml"),
+    embedded!("device/mobiles.yml"),
+    embedded!("device/notebooks.yml"),
+    embedded!("device/portable_media_player.yml"),
+    embedded!("device/shell_tv.yml"),
+    embedded!("device/televisions.yml"),
+];
+
+static EMBEDDED: Lazy<Arc<RegexDatabase>> = Lazy::new(|| {
+    let database = RegexDatabase::load(|path| {
+        EMBEDDED_FILES
+            .iter()
+            .find(|(name, _, _)| *name == path)
+            .map(|(_, contents, hash)| Source::MessagePack(contents, *hash))
+            .with_context(|| format!("{} is not embedded", path))
+    });
+
+    Arc::new(database.expect("loading embedded regexes"))
+});
+
+/// Every regex list parsed from a `regexes/` directory. Each detector holds
+/// its own, so detectors loaded from different directories can coexist.
+#[derive(Debug)]
+pub(crate) struct RegexDatabase {
+    /// A hash of every file, see [`RegexDatabase::version`].
+    version: u64,
+
+    pub(crate) bots: BotList,
+    pub(crate) oss: OSList,
+    pub(crate) vendor_fragments: VendorFragmentList,
+
+    pub(crate) browsers: BrowserClientList,
+    pub(crate) browser_engines: BrowserEngineList,
+    pub(crate) feed_readers: ClientList,
+    pub(crate) libraries: ClientList,
+    pub(crate) media_players: ClientList,
+    pub(crate) mobile_apps: ClientList,
+    pub(crate) pim: ClientList,
+    pub(crate) app_hints: HintList,
+    pub(crate) browser_hints: HintList,
+
+    pub(crate) cameras: DeviceList,
+    pub(crate) car_browsers: DeviceList,
+    pub(crate) consoles: DeviceList,
+    pub(crate) mobiles: DeviceList,
+    pub(crate) notebooks: DeviceList,
+    pub(crate) portable_media_players: DeviceList,
+    pub(crate) shell_tvs: DeviceList,
+    pub(crate) televisions: DeviceList,
+}
+
+impl RegexDatabase {
+    /// The regexes compiled into the binary. They are parsed once per process
+    /// and shared by every detector that uses them.
+    pub(crate) fn embedded() -> Arc<RegexDatabase> {
+        EMBEDDED.clone()
+    }
+
+    pub(crate) fn from_dir(dir: &Path) -> Result<RegexDatabase> {
+        Self::load(|path| {
+            let path = dir.join(path);
+            let contents = std::fs::read_to_string(&path)
+                .with_context(|| format!("reading {}", path.display()))?;
+            Ok(Source::Yaml(Cow::Owned(contents)))
+        })
+    }
+
+    /// Changes whenever any of the regexes do. Unlike the std hashers, this
+    /// is the same from one run, or build, to the next.
+    pub(crate) fn version(&self) -> u64 {
+        self.version
+    }
+
+    /// What was parsed from each file, along with its path.
+    pub(crate) fn files(&self) -> [(&'static str, &dyn std::fmt::Debug); 20] {
+        [
+            ("bots.yml", &self.bots),
+            ("oss.yml", &self.oss),
+            ("vendorfragments.yml", &self.vendor_fragments),
+            ("client/browsers.yml", &self.browsers),
+            ("client/browser_engine.yml", &self.browser_engines),
+            ("client/feed_readers.yml", &self.feed_readers),
+            ("client/libraries.yml", &self.libraries),
+            ("client/mediaplayers.yml", &self.media_players),
+            ("client/mobile_apps.yml", &self.mobile_apps),
+            ("client/pim.yml", &self.pim),
+            ("client/hints/apps.yml", &self.app_hints),
+            ("client/hints/browsers.yml", &self.browser_hints),
+            ("device/cameras.yml", &self.cameras),
+            ("device/car_browsers.yml", &self.car_browsers),
+            ("device/consoles.yml", &self.consoles),
+            ("device/mobiles.yml", &self.mobiles),
+            ("device/notebooks.yml", &self.notebooks),
+            (
+                "device/portable_media_player.yml",
+                &self.portable_media_players,
+            ),
+            ("device/shell_tv.yml", &self.shell_tvs),
+            ("device/televisions.yml", &self.televisions),
+        ]
+    }
+
+    /// Every regex in the database, along with the file it came from.
+    pub(crate) fn regexes(&self) -> impl Iterator<Item = (&'static str, &LazyRegex)> {
+        macro_rules! list {
+            ($list:expr, $path:literal) => {
+                $list.regexes().map(|regex| ($path, regex))
+            };
+        }
+
+        list!(self.bots, "bots.yml")
+            .chain(list!(self.oss, "oss.yml"))
+            .chain(list!(self.vendor_fragments, "vendorfragments.yml"))
+            .chain(list!(self.browsers, "client/browsers.yml"))
+            .chain(list!(self.browser_engines, "client/browser_engine.yml"))
+            .chain(list!(self.feed_readers, "client/feed_readers.yml"))
+            .chain(list!(self.libraries, "client/libraries.yml"))
+            .chain(list!(self.media_players, "client/mediaplayers.yml"))
+            .chain(list!(self.mobile_apps, "client/mobile_apps.yml"))
+            .chain(list!(self.pim, "client/pim.yml"))
+            .chain(list!(self.cameras, "device/cameras.yml"))
+            .chain(list!(self.car_browsers, "device/car_browsers.yml"))
+            .chain(list!(self.consoles, "device/consoles.yml"))
+            .chain(list!(self.mobiles, "device/mobiles.yml"))
+            .chain(list!(self.notebooks, "device/notebooks.yml"))
+            .chain(list!(
+                self.portable_media_players,
+                "device/portable_media_player.yml"
+            ))
+            .chain(list!(self.shell_tvs, "device/shell_tv.yml"))
+            .chain(list!(self.televisions, "device/televisions.yml"))
+    }
+
+    /// Builds every prefilter index now rather than on first use.
+    pub(crate) fn build_prefilters(&self) {
+        self.bots.build_prefilter();
+
+        for devices in [
+            &self.cameras,
+            &self.car_browsers,
+            &self.consoles,
+            &self.mobiles,
+            &self.notebooks,
+            &self.portable_media_players,
+            &self.shell_tvs,
+            &self.televisions,
+        ] {
+            devices.build_prefilter();
+        }
+    }
+
+    fn load<'a>(read: impl Fn(&str) -> Result<Source<'a>>) -> Result<RegexDatabase> {
+        // the name and yaml of every file, hashed in turn, so the embedded
+        // regexes come out the same as a directory with the same yaml
+        let version = Cell::new(0xcbf29ce484222325_u64);
+        let read = |path: &str| {
+            let source = read(path)?;
+            let hash = match &source {
+                Source::Yaml(contents) => fnv1a(contents.as_bytes()),
+                Source::MessagePack(_, hash) => *hash,
+            };
+            let mut bytes = version.get().to_le_bytes().to_vec();
+            bytes.extend_from_slice(path.as_bytes());
+            bytes.extend_from_slice(&hash.to_le_bytes());
+            version.set(fnv1a(&bytes));
+            Ok::<_, anyhow::Error>(source)
+        };
+
+        macro_rules! list {
+            ($list:ty, $path:literal) => {
+                match read($path)? {
+                    Source::Yaml(contents) => <$list>::from_file(&contents),
+                    Source::MessagePack(bytes, _) => <$list>::from_deserializer(
+                        &mut rmp_serde::Deserializer::from_read_ref(bytes),
+                    ),
+                }
+                .context(concat!("loading ", $path))?
+            };
+        }
+
+        let mut database = RegexDatabase {
+            version: 0,
+            bots: list!(BotList, "bots.yml"),
+            oss: list!(OSList, "oss.yml"),
+            vendor_fragments: list!(VendorFragmentList, "vendorfragments.yml"),
+
+            browsers: list!(BrowserClientList, "client/browsers.yml"),
+            browser_engines: list!(BrowserEngineList, "client/browser_engine.yml"),
+            feed_readers: list!(ClientList, "client/feed_readers.yml"),
+            libraries: list!(ClientList, "client/libraries.yml"),
+            media_players: list!(ClientList, "client/mediaplayers.yml"),
+            mobile_apps: list!(ClientList, "client/mobile_apps.yml"),
+            pim: list!(ClientList, "client/pim.yml"),
+            app_hints: list!(HintList, "client/hints/apps.yml"),
+            browser_hints: list!(HintList, "client/hints/browsers.yml"),
+
+            cameras: list!(DeviceList, "device/cameras.yml"),
+            car_browsers: list!(DeviceList, "device/car_browsers.yml"),
+            consoles: list!(DeviceList, "device/consoles.yml"),
+            mobiles: list!(DeviceList, "device/mobiles.yml"),
+            notebooks: list!(DeviceList, "device/notebooks.yml"),
+            portable_media_players: list!(DeviceList, "device/portable_media_player.yml"),
+            shell_tvs: list!(DeviceList, "device/shell_tv.yml"),
+            televisions: list!(DeviceList, "device/televisions.yml"),
+        };
+
+        database.version = version.get();
+        Ok(database)
+    }
+}
@@ -1,6 +1,6 @@
 use anyhow::Result;
 
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize};
 
 use serde_yaml::Value;
 
@@ -13,13 +13,18 @@ use super::vendor_fragments;
 use std::borrow::Cow;
 
 use crate::client_hints::ClientHint;
-use crate::parsers::client::{Client, ClientType};
-use crate::parsers::oss::OS;
+use crate::known_brands::AvailableBrands;
+use crate::parsers::client::ClientRef;
+use crate::parsers::database::RegexDatabase;
+use crate::parsers::oss::OSRef;
+use crate::parsers::prefilter::Prefilter;
 
 use crate::parsers::utils::{
     lazy_user_agent_match, static_user_agent_match, LazyRegex, SafeRegex as Regex,
 };
 
+static AVAILABLE_BRANDS: Lazy<AvailableBrands> = Lazy::new(AvailableBrands::default);
+
 pub mod cameras;
 pub mod car_browsers;
 pub mod consoles;
@@ -29,7 +34,7 @@ pub mod portable_media_players;
 pub mod shell_tvs;
 pub mod televisions;
 
-#[derive(Debug, PartialEq, Eq, Clone, Serialize)]
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
 pub enum DeviceType {
     #[serde(rename = "desktop")]
     Desktop,
@@ -113,6 +118,10 @@ pub struct Device {
     #[serde(rename = "type")]
     pub device_type: Option<DeviceType>,
     pub brand: Option<String>,
+    /// Matomo's code for the brand, see [`AvailableBrand::short_name`].
+    ///
+    /// [`AvailableBrand::short_name`]: crate::known_brands::AvailableBrand::short_name
+    pub short_name: Option<String>,
     pub model: Option<String>,
 
     // Can be gotten from headers, and can solely determine whether
@@ -123,9 +132,59 @@ pub struct Device {
     pub(crate) touch_enabled: bool,
 }
 
+/// A [`Device`] that borrows its brand from the regexes it was found with,
+/// rather than copying it. The model comes out of the user agent itself, so
+/// is always owned.
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct DeviceRef<'a> {
+    #[serde(rename = "type")]
+    pub device_type: Option<DeviceType>,
+    pub brand: Option<Cow<'a, str>>,
+    pub short_name: Option<Cow<'a, str>>,
+    pub model: Option<String>,
+
+    #[serde(skip)]
+    pub(crate) mobile_client_hint: bool,
+    #[serde(skip)]
+    pub(crate) touch_enabled: bool,
+}
+
+impl DeviceRef<'_> {
+    pub fn to_owned(&self) -> Device {
+        self.clone().into()
+    }
+}
+
+impl From<DeviceRef<'_>> for Device {
+    fn from(device: DeviceRef<'_>) -> Self {
+        Device {
+            device_type: device.device_type,
+            brand: device.brand.map(Cow::into_owned),
+            short_name: device.short_name.map(Cow::into_owned),
+            model: device.model,
+            mobile_client_hint: device.mobile_client_hint,
+            touch_enabled: device.touch_enabled,
+        }
+    }
+}
+
+impl From<Device> for DeviceRef<'_> {
+    fn from(device: Device) -> Self {
+        DeviceRef {
+            device_type: device.device_type,
+            brand: device.brand.map(Cow::Owned),
+            short_name: device.short_name.map(Cow::Owned),
+            model: device.model,
+            mobile_client_hint: device.mobile_client_hint,
+            touch_enabled: device.touch_enabled,
+        }
+    }
+}
+
 #[derive(Debug)]
 pub struct DeviceList {
     devices: Vec<(String, DeviceEntry)>,
+    prefilter: Prefilter,
 }
 
 #[derive(Debug)]
@@ -143,16 +202,16 @@ struct DeviceEntry {
     models: Vec<ModelEntry>,
 }
 #[derive(Debug)]
-struct DeviceMatchResult {
+struct DeviceMatchResult<'a> {
     device: Option<DeviceType>,
-    model: Option<ModelMatchResult>,
+    model: Option<ModelMatchResult<'a>>,
 }
 
 #[derive(Debug)]
-struct ModelMatchResult {
+struct ModelMatchResult<'a> {
     model: String,
     device: Option<DeviceType>,
-    brand: Option<String>,
+    brand: Option<&'a str>,
 }
 
 //This is prominently featured in the php code, but I can find no examples in which
@@ -173,12 +232,13 @@ struct ModelMatchResult {
 //    R1.is_match(ua).unwrap() && !R2.is_match(ua) && !R3.is_match(ua).unwrap() && !R4.is_match(ua).unwrap()
 //}
 
-pub fn lookup(
+pub fn lookup<'a>(
+    db: &'a RegexDatabase,
     ua: &str,
-    client: Option<&Client>,
+    client: Option<&ClientRef<'_>>,
     client_hints: Option<&ClientHint>,
-    os_info: Option<&OS>,
-) -> Result<Option<Device>> {
+    os_info: Option<&OSRef<'_>>,
+) -> Result<Option<DeviceRef<'a>>> {
     static ANDROID_10_MODEL: Lazy<Regex> =
         Lazy::new(|| Regex::new(r"(Android 10[.\d]*; K)(?: Build/|[;)])").unwrap());
 
@@ -202,37 +262,37 @@ pub fn lookup(
     };
 
     let mut device = 'dev: {
-        if let Some(res) = televisions::lookup(&ua)? {
+        if let Some(res) = televisions::lookup(db, &ua)? {
             break 'dev res;
         }
 
-        if let Some(res) = shell_tvs::lookup(&ua)? {
+        if let Some(res) = shell_tvs::lookup(db, &ua)? {
             break 'dev res;
         }
-        if let Some(res) = notebooks::lookup(&ua)? {
+        if let Some(res) = notebooks::lookup(db, &ua)? {
             break 'dev res;
         }
 
-        if let Some(res) = consoles::lookup(&ua)? {
+        if let Some(res) = consoles::lookup(db, &ua)? {
             break 'dev res;
         }
 
-        if let Some(res) = car_browsers::lookup(&ua)? {
+        if let Some(res) = car_browsers::lookup(db, &ua)? {
             break 'dev res;
         }
 
-        if let Some(res) = cameras::lookup(&ua)? {
+        if let Some(res) = cameras::lookup(db, &ua)? {
             break 'dev res;
         }
-        if let Some(res) = portable_media_players::lookup(&ua)? {
+        if let Some(res) = portable_media_players::lookup(db, &ua)? {
             break 'dev res;
         }
 
-        if let Some(res) = mobiles::lookup(&ua)? {
+        if let Some(res) = mobiles::lookup(db, &ua)? {
             break 'dev res;
         }
 
-        Device {
+        DeviceRef {
             device_type: None,
             model: None,
             brand: None,
@@ -257,8 +317,8 @@ pub fn lookup(
     }
 
     if device.brand.is_none() {
-        if let Some(brand) = vendor_fragments::lookup(&ua)? {
-            device.brand = Some(brand.to_owned());
+        if let Some(brand) = vendor_fragments::lookup(db, &ua)? {
+            device.brand = Some(Cow::Borrowed(brand));
         }
     }
 
@@ -271,10 +331,8 @@ pub fn lookup(
             }
         }
 
-        if device.brand.is_none() {
-            if APPLE_OS_NAMES.iter().any(|x| *x == os.name) {
-                device.brand = Some("Apple".to_owned());
-            }
+        if device.brand.is_none() && APPLE_OS_NAMES.iter().any(|x| *x == os.name) {
+            device.brand = Some(Cow::Borrowed("Apple"));
         }
     }
 
@@ -431,6 +489,14 @@ pub fn lookup(
         device.device_type = Some(DeviceType::Desktop);
     }
 
+    // only now that the brand is settled
+    device.short_name = device
+        .brand
+        .as_deref()
+        .and_then(|brand| AVAILABLE_BRANDS.search_by_name(brand))
+        .and_then(|brand| brand.short_name.as_deref())
+        .map(Cow::Borrowed);
+
     if device.device_type.is_none() && device.brand.is_none() && device.model.is_none() {
         Ok(None)
     } else {
@@ -438,14 +504,14 @@ pub fn lookup(
     }
 }
 
-fn is_desktop(os: Option<&OS>, client: Option<&Client>) -> bool {
+fn is_desktop(os: Option<&OSRef<'_>>, client: Option<&ClientRef<'_>>) -> bool {
     // TODO FIXME if name is "Unknown" or some variant?
     if os.is_none() {
         return false;
     }
 
     if let Some(client) = &client {
-        if uses_mobile_browser(client) {
+        if client.uses_mobile_browser() {
             return false;
         }
     }
@@ -457,16 +523,6 @@ fn is_desktop(os: Option<&OS>, client: Option<&Client>) -> bool {
     false
 }
 
-pub(crate) fn uses_mobile_browser(client: &Client) -> bool {
-    if client.r#type == ClientType::Browser {
-        if let Some(browser) = &client.browser {
-            return browser.mobile_only;
-        }
-    }
-
-    false
-}
-
 fn is_touch(ua: &str) -> Result<bool> {
     static TOUCH: Lazy<Regex> = static_user_agent_match!(r#"Touch"#);
 
@@ -475,8 +531,25 @@ fn is_touch(ua: &str) -> Result<bool> {
 }
 
 impl DeviceList {
-    fn lookup(&self, ua: &str, _type: &str) -> Result<Option<Device>> {
-        for (name, device) in self.devices.iter() {
+    pub(crate) fn regexes(&self) -> impl Iterator<Item = &LazyRegex> {
+        self.devices.iter().flat_map(|(_, device)| {
+            std::iter::once(&device.regex)
+                .chain(device.models.iter().filter_map(|model| model.regex.as_ref()))
+        })
+    }
+
+    fn patterns(&self) -> impl Iterator<Item = &str> {
+        self.devices.iter().map(|(_, device)| device.regex.pattern.as_str())
+    }
+
+    pub(crate) fn build_prefilter(&self) {
+        self.prefilter.build(|| self.patterns());
+    }
+
+    fn lookup(&self, ua: &str, _type: &str) -> Result<Option<DeviceRef<'_>>> {
+        let candidates = self.prefilter.candidates(ua, || self.patterns());
+
+        for (name, device) in candidates.into_iter().map(|i| &self.devices[i]) {
             if let Some(match_result) = device.lookup(ua)? {
                 static TD: Lazy<Regex> = Lazy::new(|| Regex::new(r#" [Tt][Dd]$"#).unwrap());
 
@@ -506,9 +579,9 @@ impl DeviceList {
                 let mut brand = match_result
                     .model
                     .as_ref()
-                    .and_then(|model| model.brand.as_ref())
-                    .or(Some(name))
-                    .cloned();
+                    .and_then(|model| model.brand)
+                    .or(Some(name.as_str()))
+                    .map(Cow::Borrowed);
 
                 if let Some(b) = &brand {
                     if b == "Unknown" {
@@ -516,7 +589,7 @@ impl DeviceList {
                     }
                 }
 
-                let dev = Device {
+                let dev = DeviceRef {
                     device_type,
                     model,
                     brand,
@@ -530,7 +603,15 @@ impl DeviceList {
         Ok(None)
     }
 
-    fn from_file(file_contents: &str) -> Result<DeviceList> {
+    pub(crate) fn from_file(file_contents: &str) -> Result<DeviceList> {
+        Self::from_deserializer(serde_yaml::Deserializer::from_str(file_contents))
+    }
+
+    pub(crate) fn from_deserializer<'de, D>(deserializer: D) -> Result<DeviceList>
+    where
+        D: Deserializer<'de>,
+        D::Error: Send + Sync + 'static,
+    {
         #[derive(Debug, Deserialize)]
         #[serde(try_from = "Value")]
         struct YamlModelEntry {
@@ -603,7 +684,10 @@ impl DeviceList {
                 // to get the right answer.
                 // devices.sort_by(|a, b| a.0.cmp(&b.0));
 
-                DeviceList { devices }
+                DeviceList {
+                    devices,
+                    prefilter: Prefilter::default(),
+                }
             }
         }
 
@@ -640,13 +724,13 @@ impl DeviceList {
             }
         }
 
-        let res: YamlDeviceList = serde_yaml::from_str(file_contents)?;
+        let res = YamlDeviceList::deserialize(deserializer)?;
         Ok(res.into())
     }
 }
 
 impl DeviceEntry {
-    fn lookup(&self, ua: &str) -> Result<Option<DeviceMatchResult>> {
+    fn lookup(&self, ua: &str) -> Result<Option<DeviceMatchResult<'_>>> {
         let res = if let Some(captures) = self.regex.captures(ua)? {
             if let Some(mut model) = self.model_match(ua)? {
                 let mut m = "".to_owned();
@@ -686,7 +770,7 @@ impl DeviceEntry {
         Ok(res)
     }
 
-    fn model_match(&self, ua: &str) -> Result<Option<ModelMatchResult>> {
+    fn model_match(&self, ua: &str) -> Result<Option<ModelMatchResult<'_>>> {
         for model in self.models.iter() {
             let res = model_match(model, ua)?;
             if res.is_some() {
@@ -698,7 +782,7 @@ impl DeviceEntry {
     }
 }
 
-fn model_match(model: &ModelEntry, ua: &str) -> Result<Option<ModelMatchResult>> {
+fn model_match<'a>(model: &'a ModelEntry, ua: &str) -> Result<Option<ModelMatchResult<'a>>> {
     let res = match &model.regex {
         Some(regex) => match regex.captures(ua)? {
             Some(caps) => {
@@ -710,7 +794,7 @@ fn model_match(model: &ModelEntry, ua: &str) -> Result<Option<ModelMatchResult>>
                 Some(ModelMatchResult {
                     model: m,
                     device: model.device.as_ref().map(|x| x.to_owned()),
-                    brand: model.brand.as_ref().map(|x| x.to_owned()),
+                    brand: model.brand.as_deref(),
                 })
             }
             _ => None,
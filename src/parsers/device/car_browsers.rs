@@ -1,16 +1,8 @@
 use anyhow::Result;
 
-use super::{Device, DeviceList};
-use once_cell::sync::Lazy;
+use super::DeviceRef;
+use crate::parsers::database::RegexDatabase;
 
-static DEVICE_LIST: Lazy<DeviceList> = Lazy::new(|| {
-    let contents = include_str!(concat!(
-        env!("CARGO_MANIFEST_DIR"),
-        "/regexes/device/car_browsers.yml"
-    ));
-    DeviceList::from_file(contents).expect("loading car_browsers.yml")
-});
-
-pub fn lookup(ua: &str) -> Result<Option<Device>> {
-    DEVICE_LIST.lookup(ua, "car browser")
+pub fn lookup<'a>(db: &'a RegexDatabase, ua: &str) -> Result<Option<DeviceRef<'a>>> {
+    db.car_browsers.lookup(ua, "car browser")
 }
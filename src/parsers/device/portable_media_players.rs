@@ -1,16 +1,9 @@
 use anyhow::Result;
 
-use super::{Device, DeviceList};
-use once_cell::sync::Lazy;
+use super::DeviceRef;
+use crate::parsers::database::RegexDatabase;
 
-static DEVICE_LIST: Lazy<DeviceList> = Lazy::new(|| {
-    let contents = include_str!(concat!(
-        env!("CARGO_MANIFEST_DIR"),
-        "/regexes/device/portable_media_player.yml"
-    ));
-    DeviceList::from_file(contents).expect("loading portable_media_player.yml")
-});
-
-pub fn lookup(ua: &str) -> Result<Option<Device>> {
-    DEVICE_LIST.lookup(ua, "portable media player")
+pub fn lookup<'a>(db: &'a RegexDatabase, ua: &str) -> Result<Option<DeviceRef<'a>>> {
+    db.portable_media_players
+        .lookup(ua, "portable media player")
 }
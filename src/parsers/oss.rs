@@ -3,20 +3,18 @@ use anyhow::Result;
 use once_cell::sync::Lazy;
 use serde_yaml::Value;
 
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize};
 
+use std::borrow::Cow;
 use std::collections::HashMap;
 
 use crate::client_hints::ClientHint;
 use crate::known_oss::AvailableOSs;
+use crate::parsers::database::RegexDatabase;
 use crate::parsers::utils::{
     lazy_user_agent_match, static_user_agent_match, LazyRegex, SafeRegex as Regex,
 };
 
-static OS_LIST: Lazy<OSList> = Lazy::new(|| {
-    let contents = include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/regexes/oss.yml"));
-    OSList::from_file(contents).expect("loading oss.yml")
-});
 static CLIENT_HINT_MAPPING: Lazy<Vec<(String, Vec<String>)>> = Lazy::new(|| {
     [("GNU/Linux", vec!["Linux"]), ("Mac", vec!["MacOS"])]
         .into_iter()
@@ -78,6 +76,8 @@ static LINEAGE_OS_VERSION: Lazy<HashMap<&str, &str>> = Lazy::new(|| {
 #[derive(Clone, Debug, Default, Serialize)]
 pub struct OS {
     pub name: String,
+    /// Matomo's code for the os, such as `AND` for Android.
+    pub short_name: Option<String>,
     pub version: Option<String>,
     pub platform: Option<String>,
     pub family: Option<String>,
@@ -86,8 +86,59 @@ pub struct OS {
     pub(crate) desktop: bool,
 }
 
-pub fn lookup(ua: &str, client_hints: Option<&ClientHint>) -> Result<Option<OS>> {
-    let mut os_from_hints: Option<OS> = client_hints.and_then(|client_hints| {
+/// An [`OS`] that borrows its name, platform and family from the regexes and
+/// known operating systems it was found with, rather than copying them. The
+/// version comes out of the user agent itself, so is always owned.
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct OSRef<'a> {
+    pub name: Cow<'a, str>,
+    pub short_name: Option<Cow<'a, str>>,
+    pub version: Option<String>,
+    pub platform: Option<Cow<'a, str>>,
+    pub family: Option<Cow<'a, str>>,
+
+    #[serde(skip)]
+    pub(crate) desktop: bool,
+}
+
+impl OSRef<'_> {
+    pub fn to_owned(&self) -> OS {
+        self.clone().into()
+    }
+}
+
+impl From<OSRef<'_>> for OS {
+    fn from(os: OSRef<'_>) -> Self {
+        OS {
+            name: os.name.into_owned(),
+            short_name: os.short_name.map(Cow::into_owned),
+            version: os.version,
+            platform: os.platform.map(Cow::into_owned),
+            family: os.family.map(Cow::into_owned),
+            desktop: os.desktop,
+        }
+    }
+}
+
+impl From<OS> for OSRef<'_> {
+    fn from(os: OS) -> Self {
+        OSRef {
+            name: Cow::Owned(os.name),
+            short_name: os.short_name.map(Cow::Owned),
+            version: os.version,
+            platform: os.platform.map(Cow::Owned),
+            family: os.family.map(Cow::Owned),
+            desktop: os.desktop,
+        }
+    }
+}
+
+pub fn lookup<'a>(
+    db: &'a RegexDatabase,
+    ua: &str,
+    client_hints: Option<&ClientHint>,
+) -> Result<Option<OSRef<'a>>> {
+    let mut os_from_hints: Option<OSRef<'a>> = client_hints.and_then(|client_hints| {
         if let Some(platform) = client_hints.platform.as_ref() {
             let hint = CLIENT_HINT_MAPPING
                 .iter()
@@ -117,11 +168,12 @@ pub fn lookup(ua: &str, client_hints: Option<&ClientHint>) -> Result<Option<OS>>
                     }
                 }
 
-                Some(OS {
-                    name: os.name.clone(),
+                Some(OSRef {
+                    name: Cow::Borrowed(&os.name),
+                    short_name: None,
                     version,
                     platform: None,
-                    family: os.family.clone(),
+                    family: os.family.as_deref().map(Cow::Borrowed),
                     desktop: os.desktop,
                 })
             } else {
@@ -132,7 +184,7 @@ pub fn lookup(ua: &str, client_hints: Option<&ClientHint>) -> Result<Option<OS>>
         }
     });
 
-    let os_from_ua: Option<OS> = OS_LIST.lookup(ua)?;
+    let os_from_ua: Option<OSRef<'a>> = db.oss.lookup(ua)?;
 
     // various occasional overrides of client hint information based on ua.
     if let Some(ref mut os_from_hints) = &mut os_from_hints {
@@ -193,17 +245,17 @@ pub fn lookup(ua: &str, client_hints: Option<&ClientHint>) -> Result<Option<OS>>
     let mut res = os_from_hints.or(os_from_ua);
 
     if let Some(os) = &mut res {
-        if let platform @ Some(_) = parse_platform(ua, client_hints)? {
-            os.platform = platform
+        if let Some(platform) = parse_platform(ua, client_hints)? {
+            os.platform = Some(Cow::Borrowed(platform));
         }
     }
 
     if let Some(os) = &mut res {
-        if let family @ Some(_) = AVAILABLE_OSSES
+        if let Some(family) = AVAILABLE_OSSES
             .search_by_name(&os.name)
-            .and_then(|x| x.family.clone())
+            .and_then(|x| x.family.as_deref())
         {
-            os.family = family;
+            os.family = Some(Cow::Borrowed(family));
         }
     }
 
@@ -219,8 +271,8 @@ pub fn lookup(ua: &str, client_hints: Option<&ClientHint>) -> Result<Option<OS>>
             if let Some(client_hints) = &client_hints {
                 if let Some(app_hint) = &client_hints.app {
                     if android_apps.iter().any(|app| *app == app_hint) {
-                        os.name = "Android".to_owned();
-                        os.family = Some("Android".to_owned());
+                        os.name = "Android".into();
+                        os.family = Some("Android".into());
                         os.version = None;
                     }
                 }
@@ -230,13 +282,13 @@ pub fn lookup(ua: &str, client_hints: Option<&ClientHint>) -> Result<Option<OS>>
         if os.name != "Lineage OS" {
             if let Some(client_hints) = &client_hints {
                 if let Some("org.lineageos.jelly") = &client_hints.app.as_deref() {
-                    os.name = "Lineage OS".to_owned();
-                    os.family = Some("Android".to_owned());
+                    os.name = "Lineage OS".into();
+                    os.family = Some("Android".into());
                     os.version = LINEAGE_OS_VERSION
                         .get(os.version.as_deref().unwrap_or_else(|| {
                             os.version
                                 .as_deref()
-                                .map(|x| x.split('.').last().unwrap_or("0"))
+                                .map(|x| x.split('.').next_back().unwrap_or("0"))
                                 .unwrap_or("0")
                         }))
                         .map(|x| (*x).to_owned());
@@ -247,8 +299,8 @@ pub fn lookup(ua: &str, client_hints: Option<&ClientHint>) -> Result<Option<OS>>
         if os.name != "Fire OS" {
             if let Some(client_hints) = &client_hints {
                 if let Some("org.mozilla.tv.firefox") = &client_hints.app.as_deref() {
-                    os.name = "Fire OS".to_owned();
-                    os.family = Some("Android".to_owned());
+                    os.name = "Fire OS".into();
+                    os.family = Some("Android".into());
                     os.version = FIRE_OS_VERSION
                         .get(os.version.as_deref().unwrap_or_else(|| {
                             os.version
@@ -260,50 +312,55 @@ pub fn lookup(ua: &str, client_hints: Option<&ClientHint>) -> Result<Option<OS>>
                 }
             }
         }
+
+        // only now that the name is settled
+        os.short_name = AVAILABLE_OSSES
+            .search_by_name(&os.name)
+            .map(|x| Cow::Borrowed(x.short_name.as_str()));
     }
 
     Ok(res)
 }
 
-fn parse_platform(ua: &str, client_hints: Option<&ClientHint>) -> Result<Option<String>> {
+fn parse_platform(ua: &str, client_hints: Option<&ClientHint>) -> Result<Option<&'static str>> {
     if let Some(client_hints) = client_hints {
         if let Some(architecture) = &client_hints.architecture {
             let arch = architecture.to_lowercase();
 
             if arch.contains("arm") {
-                return Ok(Some("ARM".into()));
+                return Ok(Some("ARM"));
             }
 
             if arch.contains("loongarch64") {
-                return Ok(Some("LoongArch64".into()));
+                return Ok(Some("LoongArch64"));
             }
 
             if arch.contains("mips") {
-                return Ok(Some("MIPS".into()));
+                return Ok(Some("MIPS"));
             }
 
             if arch.contains("sh4") {
-                return Ok(Some("SuperH".into()));
+                return Ok(Some("SuperH"));
             }
 
             if arch.contains("sparc64") {
-                return Ok(Some("SPARC64".into()));
+                return Ok(Some("SPARC64"));
             }
 
             if arch.contains("x64") {
-                return Ok(Some("x64".into()));
+                return Ok(Some("x64"));
             }
 
             if arch.contains("x86") {
                 if let Some(bitness) = &client_hints.bitness {
                     if bitness == "64" {
-                        return Ok(Some("x64".into()));
+                        return Ok(Some("x64"));
                     }
                 }
             }
 
             if arch.contains("x86") {
-                return Ok(Some("x86".into()));
+                return Ok(Some("x86"));
             }
         }
     }
@@ -319,37 +376,38 @@ fn parse_platform(ua: &str, client_hints: Option<&ClientHint>) -> Result<Option<
     static X86_REG: Lazy<Regex> = static_user_agent_match!(".*32bit|.*win32|(?:i[0-9]|x)86|i86pc");
 
     if ARM_REG.is_match(ua)? {
-        return Ok(Some("ARM".into()));
+        return Ok(Some("ARM"));
     }
 
     if LONGARCH64_REG.is_match(ua)? {
-        return Ok(Some("LoongArch64".into()));
+        return Ok(Some("LoongArch64"));
     }
 
     if MIPS_REG.is_match(ua)? {
-        return Ok(Some("MIPS".into()));
+        return Ok(Some("MIPS"));
     }
 
     if SH4_REG.is_match(ua)? {
-        return Ok(Some("SuperH".into()));
+        return Ok(Some("SuperH"));
     }
 
     if SPARC64_REG.is_match(ua)? {
-        return Ok(Some("SPARC64".into()));
+        return Ok(Some("SPARC64"));
     }
 
     if X64_REG.is_match(ua)? {
-        return Ok(Some("x64".into()));
+        return Ok(Some("x64"));
     }
 
     if X86_REG.is_match(ua)? {
-        return Ok(Some("x86".into()));
+        return Ok(Some("x86"));
     }
 
     Ok(None)
 }
 
-struct OSList {
+#[derive(Debug)]
+pub(crate) struct OSList {
     oss: Vec<OSEntry>,
 }
 
@@ -368,15 +426,15 @@ struct OSVersion {
 }
 
 impl OSEntry {
-    fn is_match(&self, ua: &str) -> Result<Option<OS>> {
+    fn is_match(&self, ua: &str) -> Result<Option<OSRef<'_>>> {
         if self.regex.is_match(ua)? {
-            let mut name = "".to_owned();
+            let mut name = Cow::Borrowed("");
             let mut v = "".to_owned();
             if let Some(captures) = self.regex.captures(ua)? {
-                captures.expand(&self.name, &mut name);
+                name = captures.expand_cow(&self.name);
 
                 if let Some(res) = AVAILABLE_OSSES.search_by_name(&name) {
-                    name = res.name.to_owned();
+                    name = Cow::Borrowed(&res.name);
                 }
 
                 for version in &self.versions {
@@ -404,14 +462,14 @@ impl OSEntry {
 
             let v = if !v.is_empty() { Some(v) } else { None };
 
-            let mut os = OS {
+            let mut os = OSRef {
                 name,
                 version: v,
                 ..Default::default()
             };
 
             if let Some(av_os) = AVAILABLE_OSSES.search_by_name(&os.name) {
-                os.family = av_os.family.clone();
+                os.family = av_os.family.as_deref().map(Cow::Borrowed);
                 os.desktop = av_os.desktop;
             }
 
@@ -422,7 +480,13 @@ impl OSEntry {
 }
 
 impl OSList {
-    fn lookup(&self, ua: &str) -> Result<Option<OS>> {
+    pub(crate) fn regexes(&self) -> impl Iterator<Item = &LazyRegex> {
+        self.oss.iter().flat_map(|os| {
+            std::iter::once(&os.regex).chain(os.versions.iter().map(|version| &version.regex))
+        })
+    }
+
+    fn lookup(&self, ua: &str) -> Result<Option<OSRef<'_>>> {
         for os in self.oss.iter() {
             if let Some(res) = os.is_match(ua)? {
                 return Ok(Some(res));
@@ -432,7 +496,15 @@ impl OSList {
         Ok(None)
     }
 
-    fn from_file(contents: &str) -> Result<OSList> {
+    pub(crate) fn from_file(contents: &str) -> Result<OSList> {
+        Self::from_deserializer(serde_yaml::Deserializer::from_str(contents))
+    }
+
+    pub(crate) fn from_deserializer<'de, D>(deserializer: D) -> Result<OSList>
+    where
+        D: Deserializer<'de>,
+        D::Error: Send + Sync + 'static,
+    {
         #[derive(Debug, Deserialize)]
         #[serde(try_from = "Value")]
         struct YamlVersion {
@@ -518,7 +590,7 @@ impl OSList {
             }
         }
 
-        let res: YamlOSList = serde_yaml::from_str(contents)?;
+        let res = YamlOSList::deserialize(deserializer)?;
         Ok(res.into())
     }
 }
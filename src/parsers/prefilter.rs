@@ -0,0 +1,212 @@
+use aho_corasick::AhoCorasick;
+
+use fancy_regex::Expr;
+
+use once_cell::sync::OnceCell;
+
+use std::collections::HashMap;
+
+/// Narrows a list of regexes down to those that could possibly match a user
+/// agent, so that only those have to be run.
+///
+/// Nearly every pattern requires some literal text to be present (a brand or
+/// model name, usually). All of those literals are found in a single pass over
+/// the user agent, and only the entries whose literal turned up, plus those
+/// for which no literal could be extracted, are candidates. The candidates
+/// are returned in their original order, so first match wins as before.
+///
+/// The index is built on first use, as building it means parsing every
+/// pattern.
+#[derive(Default)]
+pub(crate) struct Prefilter {
+    index: OnceCell<Index>,
+}
+
+struct Index {
+    entries: usize,
+    literals: AhoCorasick,
+    // for each literal, the entries that require it
+    entries_by_literal: Vec<Vec<usize>>,
+    // entries with no required literal, which are always candidates
+    always: Vec<usize>,
+}
+
+impl std::fmt::Debug for Prefilter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("Prefilter")
+    }
+}
+
+impl Prefilter {
+    fn index<'a, I>(&self, patterns: impl FnOnce() -> I) -> &Index
+    where
+        I: IntoIterator<Item = &'a str>,
+    {
+        self.index.get_or_init(|| Index::new(patterns()))
+    }
+
+    /// Builds the index now rather than on first use.
+    pub(crate) fn build<'a, I>(&self, patterns: impl FnOnce() -> I)
+    where
+        I: IntoIterator<Item = &'a str>,
+    {
+        self.index(patterns);
+    }
+
+    /// The indices of the entries whose pattern could match `ua`, in order.
+    /// `patterns` must always produce the same patterns, in the same order.
+    pub(crate) fn candidates<'a, I>(&self, ua: &str, patterns: impl FnOnce() -> I) -> Vec<usize>
+    where
+        I: IntoIterator<Item = &'a str>,
+    {
+        let index = self.index(patterns);
+
+        // With case insensitivity, regex will match some non ascii characters
+        // against ascii literals, such as the kelvin sign against k. Rather
+        // than deal with that, just try everything.
+        if !ua.is_ascii() {
+            return (0..index.entries).collect();
+        }
+
+        let mut candidate = vec![false; index.entries];
+        let mut seen = vec![false; index.entries_by_literal.len()];
+
+        for &entry in index.always.iter() {
+            candidate[entry] = true;
+        }
+
+        for m in index.literals.find_overlapping_iter(ua) {
+            let literal = m.pattern().as_usize();
+            if seen[literal] {
+                continue;
+            }
+            seen[literal] = true;
+
+            for &entry in index.entries_by_literal[literal].iter() {
+                candidate[entry] = true;
+            }
+        }
+
+        candidate
+            .into_iter()
+            .enumerate()
+            .filter_map(|(entry, candidate)| candidate.then_some(entry))
+            .collect()
+    }
+}
+
+impl Index {
+    fn new<'a>(patterns: impl IntoIterator<Item = &'a str>) -> Self {
+        let mut literals: Vec<String> = Vec::new();
+        let mut literal_ids: HashMap<String, usize> = HashMap::new();
+        let mut entries_by_literal: Vec<Vec<usize>> = Vec::new();
+        let mut always = Vec::new();
+        let mut entries = 0;
+
+        for (entry, pattern) in patterns.into_iter().enumerate() {
+            entries += 1;
+
+            let required = Expr::parse_tree(pattern)
+                .ok()
+                .and_then(|tree| required_literals(&tree.expr));
+
+            let Some(required) = required else {
+                always.push(entry);
+                continue;
+            };
+
+            for literal in required {
+                let literal = literal.to_ascii_lowercase();
+                let id = *literal_ids.entry(literal.clone()).or_insert_with(|| {
+                    literals.push(literal);
+                    entries_by_literal.push(Vec::new());
+                    literals.len() - 1
+                });
+
+                let by_literal = &mut entries_by_literal[id];
+                if by_literal.last() != Some(&entry) {
+                    by_literal.push(entry);
+                }
+            }
+        }
+
+        let literals = AhoCorasick::builder()
+            .ascii_case_insensitive(true)
+            .build(&literals)
+            .expect("prefilter literals");
+
+        Index {
+            entries,
+            literals,
+            entries_by_literal,
+            always,
+        }
+    }
+}
+
+/// A set of literals, at least one of which must be in any text the
+/// expression matches. `None` if there is no such set.
+///
+/// Only ascii literals are used, see [`Prefilter::candidates`].
+fn required_literals(expr: &Expr) -> Option<Vec<String>> {
+    match expr {
+        Expr::Literal { val, .. } if !val.is_empty() && val.is_ascii() => Some(vec![val.clone()]),
+
+        Expr::Concat(exprs) => {
+            let mut best: Option<Vec<String>> = None;
+            let mut consider = |literals: Option<Vec<String>>| {
+                if let Some(literals) = literals {
+                    if best
+                        .as_ref()
+                        .is_none_or(|best| selectivity(&literals) > selectivity(best))
+                    {
+                        best = Some(literals);
+                    }
+                }
+            };
+
+            // consecutive literals are parsed one character at a time, so
+            // join them back up into the longest possible run.
+            let mut run = String::new();
+            for expr in exprs {
+                match expr {
+                    Expr::Literal { val, .. } if val.is_ascii() => run.push_str(val),
+                    expr => {
+                        if !run.is_empty() {
+                            consider(Some(vec![std::mem::take(&mut run)]));
+                        }
+                        consider(required_literals(expr));
+                    }
+                }
+            }
+            if !run.is_empty() {
+                consider(Some(vec![run]));
+            }
+
+            best
+        }
+
+        // every branch has to have a literal, otherwise the branch without
+        // one could match anything.
+        Expr::Alt(exprs) => {
+            let mut literals = Vec::new();
+            for expr in exprs {
+                literals.extend(required_literals(expr)?);
+            }
+            Some(literals)
+        }
+
+        Expr::Group(expr) | Expr::AtomicGroup(expr) => required_literals(expr),
+
+        Expr::Repeat { child, lo, .. } if *lo > 0 => required_literals(child),
+
+        _ => None,
+    }
+}
+
+/// Longer literals are less likely to be found by chance, and so rule out
+/// more entries. Fewer alternatives are better for the same reason.
+fn selectivity(literals: &[String]) -> (usize, std::cmp::Reverse<usize>) {
+    let shortest = literals.iter().map(|x| x.len()).min().unwrap_or(0);
+    (shortest, std::cmp::Reverse(literals.len()))
+}
@@ -1,59 +1,237 @@
 use anyhow::Result;
 
-use fancy_regex::{CaptureMatches, Captures, Error, Expander, Regex, Replacer};
+use fancy_regex::{Error, Expr, Regex};
 use once_cell::sync::Lazy;
 
 use once_cell::sync::OnceCell;
 
-#[derive(Debug)]
+use crate::diagnostics;
+use crate::error;
+
 pub(crate) struct LazyRegex {
     pub(crate) pattern: String,
     regex: OnceCell<SafeRegex>,
 }
+
+// Whether or not it has been compiled yet is an implementation detail, the
+// pattern is all that matters.
+impl std::fmt::Debug for LazyRegex {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("LazyRegex").field(&self.pattern).finish()
+    }
+}
 use std::collections::HashMap;
 use std::sync::Arc;
 use std::sync::RwLock;
 
 use std::borrow::Cow;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+static PLAIN_REGEXES: AtomicUsize = AtomicUsize::new(0);
+static FANCY_REGEXES: AtomicUsize = AtomicUsize::new(0);
+
+/// How many regexes have been compiled so far by each engine, across the
+/// whole process. Regexes are compiled lazily, the first time they are needed.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct RegexStats {
+    /// Compiled with the regex crate, which runs in linear time.
+    pub plain: usize,
+    /// Needed look around or backreferences, so compiled with fancy_regex,
+    /// which backtracks.
+    pub fancy: usize,
+}
+
+pub(crate) fn regex_stats() -> RegexStats {
+    RegexStats {
+        plain: PLAIN_REGEXES.load(Ordering::Relaxed),
+        fancy: FANCY_REGEXES.load(Ordering::Relaxed),
+    }
+}
 
 /// This is a regex that won't crash due to run time errors on match.
 /// This can still crash if passed an invalid regex in the first place.
+///
+/// Most patterns don't use anything the regex crate can't do, and those are
+/// compiled with it, so they can't backtrack at all. Only the rest go to
+/// fancy_regex.
 #[derive(Debug)]
-pub(crate) struct SafeRegex {
-    regex: Regex,
+pub(crate) enum SafeRegex {
+    Plain(regex::Regex),
+    Fancy(Regex),
+}
+
+/// The captures of either kind of [`SafeRegex`].
+pub(crate) enum Captures<'t> {
+    Plain(regex::Captures<'t>),
+    Fancy(fancy_regex::Captures<'t>),
+}
+
+pub(crate) struct Match<'t> {
+    text: &'t str,
+}
+
+impl<'t> Match<'t> {
+    pub fn as_str(&self) -> &'t str {
+        self.text
+    }
+}
+
+impl<'t> Captures<'t> {
+    pub fn get(&self, i: usize) -> Option<Match<'t>> {
+        let text = match self {
+            Self::Plain(caps) => caps.get(i).map(|x| x.as_str()),
+            Self::Fancy(caps) => caps.get(i).map(|x| x.as_str()),
+        };
+        text.map(|text| Match { text })
+    }
+
+    /// Both engines expand `$1`, `${1}` and `$$` the same way.
+    pub fn expand(&self, template: &str, dst: &mut String) {
+        match self {
+            Self::Plain(caps) => caps.expand(template, dst),
+            Self::Fancy(caps) => caps.expand(template, dst),
+        }
+    }
+
+    /// Like [`Captures::expand`], but borrows templates without any `$` in
+    /// them, which is most of them, as they are.
+    pub fn expand_cow<'a>(&self, template: &'a str) -> Cow<'a, str> {
+        if !template.contains('$') {
+            return Cow::Borrowed(template);
+        }
+
+        let mut dst = String::new();
+        self.expand(template, &mut dst);
+        Cow::Owned(dst)
+    }
+}
+
+impl std::ops::Index<usize> for Captures<'_> {
+    type Output = str;
+
+    fn index(&self, i: usize) -> &str {
+        match self {
+            Self::Plain(caps) => &caps[i],
+            Self::Fancy(caps) => &caps[i],
+        }
+    }
+}
+
+/// Whether fancy_regex would need its backtracking vm for this expression,
+/// the same as its own analysis. Word boundaries count, because fancy_regex
+/// can't hand them to the regex crate.
+fn needs_fancy(expr: &Expr) -> bool {
+    match expr {
+        Expr::Empty | Expr::Any { .. } | Expr::Literal { .. } | Expr::Delegate { .. } => false,
+        Expr::Assertion(assertion) => matches!(
+            assertion,
+            fancy_regex::Assertion::LeftWordBoundary
+                | fancy_regex::Assertion::RightWordBoundary
+                | fancy_regex::Assertion::WordBoundary
+                | fancy_regex::Assertion::NotWordBoundary
+        ),
+        Expr::Concat(exprs) | Expr::Alt(exprs) => exprs.iter().any(needs_fancy),
+        Expr::Group(expr) => needs_fancy(expr),
+        Expr::Repeat { child, .. } => needs_fancy(child),
+        _ => true,
+    }
 }
 
 impl SafeRegex {
-    fn squash_runtime_error<T>(err: Result<T, Error>, ret: T) -> Result<T, Error> {
+    fn runtime_error(regex: &Regex, err: Error) -> anyhow::Error {
+        error::Error::RegexRuntime {
+            pattern: regex.as_str().to_owned(),
+            message: err.to_string(),
+        }
+        .into()
+    }
+
+    fn squash_runtime_error<T>(regex: &Regex, err: Result<T, Error>, ret: T) -> Result<T> {
         // this is either a stack overflow or a backtrack limit reached.
-        // in either case, we don't want to crash, just deny a match and move on.
+        // in either case, we don't want to crash, just deny a match and move on,
+        // but do let whoever is watching know.
         match err {
-            Err(Error::RuntimeError(_)) => Ok(ret),
-            err => err,
+            Err(Error::RuntimeError(_)) => {
+                diagnostics::regex_failed(regex.as_str());
+                Ok(ret)
+            }
+            Err(err) => Err(Self::runtime_error(regex, err)),
+            Ok(res) => Ok(res),
         }
     }
 
     pub fn new(pattern: &str) -> Result<Self> {
-        let regex = Regex::new(pattern)?;
-        Ok(Self { regex })
+        Self::compile(pattern).map_err(|err| {
+            error::Error::InvalidRegex {
+                pattern: pattern.to_owned(),
+                message: format!("{:#}", err),
+            }
+            .into()
+        })
+    }
+
+    fn compile(pattern: &str) -> Result<Self> {
+        let tree = Expr::parse_tree(pattern)?;
+
+        if !tree.named_groups.is_empty() || needs_fancy(&tree.expr) {
+            let regex = Regex::new(pattern)?;
+            FANCY_REGEXES.fetch_add(1, Ordering::Relaxed);
+            return Ok(Self::Fancy(regex));
+        }
+
+        // fancy_regex's syntax isn't quite the same as the regex crate's, but
+        // it knows how to translate, which is what it does itself for
+        // patterns like these.
+        let mut translated = String::new();
+        tree.expr.to_str(&mut translated, 0);
+
+        let regex = regex::Regex::new(&translated)?;
+        PLAIN_REGEXES.fetch_add(1, Ordering::Relaxed);
+        Ok(Self::Plain(regex))
     }
 
     pub fn is_match(&self, text: &str) -> Result<bool> {
-        let res = Self::squash_runtime_error(self.regex.is_match(text), false)?;
-        Ok(res)
+        match self {
+            Self::Plain(regex) => Ok(regex.is_match(text)),
+            Self::Fancy(regex) => Self::squash_runtime_error(regex, regex.is_match(text), false),
+        }
     }
 
     pub fn captures<'t>(&self, text: &'t str) -> Result<Option<Captures<'t>>> {
-        let res = Self::squash_runtime_error(self.regex.captures(text), None)?;
-        Ok(res)
+        match self {
+            Self::Plain(regex) => Ok(regex.captures(text).map(Captures::Plain)),
+            Self::Fancy(regex) => {
+                let res = Self::squash_runtime_error(regex, regex.captures(text), None)?;
+                Ok(res.map(Captures::Fancy))
+            }
+        }
     }
 
-    pub fn replace_all<'t, R: Replacer>(&self, text: &'t str, rep: R) -> Cow<'t, str> {
-        self.regex.replace_all(text, rep)
+    pub fn replace_all<'t>(&self, text: &'t str, rep: &str) -> Cow<'t, str> {
+        match self {
+            Self::Plain(regex) => regex.replace_all(text, rep),
+            Self::Fancy(regex) => regex.replace_all(text, rep),
+        }
     }
 
-    pub fn captures_iter<'r, 'h>(&'r self, haystack: &'h str) -> CaptureMatches<'r, 'h> {
-        self.regex.captures_iter(haystack)
+    pub fn captures_iter<'r, 'h>(
+        &'r self,
+        haystack: &'h str,
+    ) -> Box<dyn Iterator<Item = Result<Captures<'h>>> + 'r>
+    where
+        'h: 'r,
+    {
+        match self {
+            Self::Plain(regex) => Box::new(
+                regex
+                    .captures_iter(haystack)
+                    .map(|caps| Ok(Captures::Plain(caps))),
+            ),
+            Self::Fancy(regex) => Box::new(regex.captures_iter(haystack).map(|caps| match caps {
+                Ok(caps) => Ok(Captures::Fancy(caps)),
+                Err(err) => Err(Self::runtime_error(regex, err)),
+            })),
+        }
     }
 }
 
@@ -70,20 +248,27 @@ impl LazyRegex {
         }
     }
 
-    pub(crate) fn is_match(&self, text: &str) -> Result<bool> {
-        let regex = self.regex.get_or_try_init(|| {
-            // println!("is_match compilation: {}", &self.pattern);
+    fn regex(&self) -> Result<&SafeRegex> {
+        self.regex.get_or_try_init(|| {
+            // println!("compilation: {}", &self.pattern);
             SafeRegex::new(&self.pattern)
-        })?;
-        regex.is_match(text)
+        })
+    }
+
+    /// Compiles the regex now rather than on first use. Returns whether it
+    /// had to be compiled, as opposed to already having been.
+    pub(crate) fn compile(&self) -> Result<bool> {
+        let compiled = self.regex.get().is_none();
+        self.regex()?;
+        Ok(compiled)
+    }
+
+    pub(crate) fn is_match(&self, text: &str) -> Result<bool> {
+        self.regex()?.is_match(text)
     }
 
     pub(crate) fn captures<'t>(&self, text: &'t str) -> Result<Option<Captures<'t>>> {
-        let regex = self.regex.get_or_try_init(|| {
-            // println!("captures compilation: {}", &self.pattern);
-            SafeRegex::new(&self.pattern)
-        })?;
-        regex.captures(text)
+        self.regex()?.captures(text)
     }
 }
 
@@ -160,8 +345,10 @@ pub(crate) fn expand(template: &str, dst: &mut String, captures: &Captures<'_>)
     static RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"\$([1-9])").unwrap());
 
     //dbg!(template);
-    let template = RE.replace_all(template, |caps: &Captures<'_>| format!("${{{}}}", &caps[1]));
+    let template = RE.replace_all(template, |caps: &fancy_regex::Captures<'_>| {
+        format!("${{{}}}", &caps[1])
+    });
 
     //dbg!(&dst, &*template, &captures);
-    Expander::default().append_expansion(dst, &template, captures);
+    captures.expand(&template, dst);
 }
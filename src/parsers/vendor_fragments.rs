@@ -1,10 +1,10 @@
 use anyhow::Result;
-use serde::Deserialize;
+use serde::{Deserialize, Deserializer};
 
-use once_cell::sync::Lazy;
-use std::collections::HashMap;
+use indexmap::IndexMap;
 
 use super::utils::lazy_user_agent_match;
+use crate::parsers::database::RegexDatabase;
 use crate::parsers::utils::LazyRegex;
 
 #[derive(Debug)]
@@ -13,20 +13,12 @@ struct VendorFragments {
     fragments: Vec<LazyRegex>,
 }
 
-static FRAGMENT_LIST: Lazy<VendorFragmentList> = Lazy::new(|| {
-    let contents = include_str!(concat!(
-        env!("CARGO_MANIFEST_DIR"),
-        "/regexes/vendorfragments.yml"
-    ));
-    VendorFragmentList::from_file(contents).expect("loading vendorfragments.yml")
-});
-
-pub fn lookup(ua: &str) -> Result<Option<&str>> {
-    FRAGMENT_LIST.lookup(ua)
+pub fn lookup<'a>(db: &'a RegexDatabase, ua: &str) -> Result<Option<&'a str>> {
+    db.vendor_fragments.lookup(ua)
 }
 
 #[derive(Debug)]
-struct VendorFragmentList {
+pub(crate) struct VendorFragmentList {
     list: Vec<VendorFragments>,
 }
 
@@ -42,6 +34,10 @@ impl VendorFragments {
 }
 
 impl VendorFragmentList {
+    pub(crate) fn regexes(&self) -> impl Iterator<Item = &LazyRegex> {
+        self.list.iter().flat_map(|x| x.fragments.iter())
+    }
+
     fn lookup(&self, ua: &str) -> Result<Option<&str>> {
         for x in self.list.iter() {
             if x.is_match(ua)? {
@@ -51,11 +47,19 @@ impl VendorFragmentList {
         Ok(None)
     }
 
-    fn from_file(contents: &str) -> Result<VendorFragmentList> {
+    pub(crate) fn from_file(contents: &str) -> Result<VendorFragmentList> {
+        Self::from_deserializer(serde_yaml::Deserializer::from_str(contents))
+    }
+
+    pub(crate) fn from_deserializer<'de, D>(deserializer: D) -> Result<VendorFragmentList>
+    where
+        D: Deserializer<'de>,
+        D::Error: Send + Sync + 'static,
+    {
         #[derive(Debug, Deserialize)]
         #[serde(transparent)]
         struct YamlVendorFragmentList {
-            list: HashMap<String, Vec<String>>,
+            list: IndexMap<String, Vec<String>>,
         }
 
         #[allow(clippy::from_over_into)]
@@ -80,7 +84,7 @@ impl VendorFragmentList {
             }
         }
 
-        let res: YamlVendorFragmentList = serde_yaml::from_str(contents)?;
+        let res = YamlVendorFragmentList::deserialize(deserializer)?;
         Ok(res.into())
     }
 }
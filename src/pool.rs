@@ -0,0 +1,65 @@
+use once_cell::sync::Lazy;
+
+use std::panic::AssertUnwindSafe;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+
+type Job = Box<dyn FnOnce() + Send>;
+
+/// Threads shared by every [`DeviceDetector::parse_batch`], so a batch
+/// doesn't have to spawn threads of its own. They are started as they are
+/// first needed, up to the most any batch has asked for, and then kept
+/// waiting for the next job for the rest of the process.
+///
+/// [`DeviceDetector::parse_batch`]: crate::device_detector::DeviceDetector::parse_batch
+pub(crate) struct Pool {
+    jobs: Mutex<Sender<Job>>,
+    queue: Arc<Mutex<Receiver<Job>>>,
+    threads: Mutex<usize>,
+}
+
+static POOL: Lazy<Pool> = Lazy::new(|| {
+    let (jobs, queue) = mpsc::channel();
+    Pool {
+        jobs: Mutex::new(jobs),
+        queue: Arc::new(Mutex::new(queue)),
+        threads: Mutex::new(0),
+    }
+});
+
+pub(crate) fn pool() -> &'static Pool {
+    &POOL
+}
+
+impl Pool {
+    /// Runs `job` on one of the threads, once one is free, making sure there
+    /// are at least `threads` of them first.
+    pub(crate) fn execute(&self, threads: usize, job: impl FnOnce() + Send + 'static) {
+        self.grow(threads);
+        self.jobs
+            .lock()
+            .unwrap()
+            .send(Box::new(job))
+            .expect("pool threads never stop");
+    }
+
+    fn grow(&self, threads: usize) {
+        let mut running = self.threads.lock().unwrap();
+        while *running < threads {
+            let queue = self.queue.clone();
+            std::thread::Builder::new()
+                .name(format!("rdd-batch-{}", *running))
+                .spawn(move || loop {
+                    let job = queue.lock().unwrap().recv();
+                    let Ok(job) = job else {
+                        return;
+                    };
+                    // whoever handed in the job finds out it panicked from
+                    // what it didn't get back, this thread carries on.
+                    let _ = std::panic::catch_unwind(AssertUnwindSafe(job));
+                })
+                .expect("spawning a batch thread");
+            *running += 1;
+        }
+    }
+}
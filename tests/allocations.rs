@@ -0,0 +1,51 @@
+// Counts every allocation in the process, so this is a test binary of its own
+// with a single test in it, that nothing else allocates alongside.
+
+use anyhow::Result;
+
+use stats_alloc::{Region, StatsAlloc, INSTRUMENTED_SYSTEM};
+
+use std::alloc::System;
+
+use rust_device_detector::device_detector::DeviceDetector;
+
+#[global_allocator]
+static GLOBAL: &StatsAlloc<System> = &INSTRUMENTED_SYSTEM;
+
+#[test]
+fn test_parse_ref_allocates_less_than_parse() -> Result<()> {
+    let dd = DeviceDetector::new();
+
+    let uas = [
+        "Mozilla/5.0 (Linux; Android 13; SM-S908B) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/112.0.0.0 Mobile Safari/537.36",
+        "Mozilla/5.0 (iPhone; CPU iPhone OS 17_0 like Mac OS X) AppleWebKit/605.1.15 (KHTML, like Gecko) Version/17.0 Mobile/15E148 Safari/604.1",
+        "Mozilla/5.0 (compatible; Googlebot/2.1; +http://www.google.com/bot.html)",
+    ];
+
+    // compile every regex these need first, so only the detections count.
+    for ua in uas {
+        dd.parse(ua, None)?;
+    }
+
+    for ua in uas {
+        let region = Region::new(GLOBAL);
+        let owned = dd.parse(ua, None)?;
+        let owned_allocations = region.change().allocations;
+        drop(owned);
+
+        let region = Region::new(GLOBAL);
+        let borrowed = dd.parse_ref(ua, None)?;
+        let borrowed_allocations = region.change().allocations;
+        drop(borrowed);
+
+        assert!(
+            borrowed_allocations < owned_allocations,
+            "{}: parse_ref allocated {} times, parse {} times",
+            ua,
+            borrowed_allocations,
+            owned_allocations
+        );
+    }
+
+    Ok(())
+}
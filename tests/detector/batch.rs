@@ -0,0 +1,56 @@
+use anyhow::Result;
+use serde_yaml::Value;
+
+use crate::utils;
+
+#[test]
+fn test_parse_batch() -> Result<()> {
+    let cases: Value =
+        serde_yaml::from_str(&std::fs::read_to_string("tests/data/fixtures/desktop.yml")?)?;
+
+    let requests: Vec<_> = cases
+        .as_sequence()
+        .expect("sequence")
+        .iter()
+        .map(|case| case["user_agent"].as_str().expect("user_agent"))
+        .chain(std::iter::once("Googlebot/2.1"))
+        .map(|ua| (ua, None::<Vec<(String, String)>>))
+        .collect();
+
+    let results = utils::DD.parse_batch(&requests, 3);
+    assert_eq!(results.len(), requests.len());
+
+    // in the same order they went in
+    for ((ua, _), result) in requests.iter().zip(results) {
+        assert_eq!(
+            result?.to_value(),
+            utils::DD.parse(ua, None)?.to_value(),
+            "ua: {}",
+            ua
+        );
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_parse_batch_sizes() -> Result<()> {
+    let none: Vec<(&str, _)> = Vec::new();
+    assert!(utils::DD.parse_batch(&none, 4).is_empty());
+
+    // more threads than there is work for, over and over, on the same pool
+    let requests = [
+        ("curl/8.0", None),
+        ("Wget/1.21", None),
+        ("Googlebot/2.1", None),
+    ];
+    for _ in 0..50 {
+        let results = utils::DD.parse_batch(&requests, 8);
+        assert_eq!(results.len(), requests.len());
+        for ((ua, _), result) in requests.iter().zip(results) {
+            assert_eq!(result?.to_value(), utils::DD.parse(ua, None)?.to_value());
+        }
+    }
+
+    Ok(())
+}
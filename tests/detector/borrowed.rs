@@ -0,0 +1,78 @@
+use anyhow::Result;
+
+use std::borrow::Cow;
+use std::sync::Arc;
+
+use rust_device_detector::cache::ShardedCache;
+use rust_device_detector::device_detector::DeviceDetector;
+
+use crate::utils;
+
+#[test]
+fn test_parse_ref() -> Result<()> {
+    let ua = "Mozilla/5.0 (compatible; Googlebot/2.1; +http://www.google.com/bot.html)";
+    let detection = utils::DD.parse_ref(ua, None)?;
+    let bot = detection.get_bot().expect("a bot");
+    assert!(matches!(bot.name, Cow::Borrowed("Googlebot")));
+    assert_eq!(
+        detection.to_owned().to_value(),
+        utils::DD.parse(ua, None)?.to_value()
+    );
+
+    let ua = "Spotify/8.6.72 iOS/13.5.1 (iPhone9,2)";
+    assert_eq!(
+        utils::DD.parse_ref(ua, None)?.to_value(),
+        utils::DD.parse(ua, None)?.to_value()
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_parse_ref_known_device() -> Result<()> {
+    let ua = "Mozilla/5.0 (Linux; Android 13; SM-S908B) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/112.0.0.0 Mobile Safari/537.36";
+    let detection = utils::DD.parse_ref(ua, None)?;
+    let known = detection.get_known_device().expect("a known device");
+
+    let client = known.client.as_ref().expect("a client");
+    assert!(matches!(client.name, Cow::Borrowed("Chrome Mobile")));
+    assert!(matches!(client.short_name, Some(Cow::Borrowed("CM"))));
+    assert!(matches!(client.engine, Some(Cow::Borrowed("Blink"))));
+    assert_eq!(client.version.as_deref(), Some("112.0.0.0"));
+
+    let os = known.os.as_ref().expect("an os");
+    assert!(matches!(os.name, Cow::Borrowed("Android")));
+    assert!(matches!(os.family, Some(Cow::Borrowed("Android"))));
+
+    let device = known.device.as_ref().expect("a device");
+    assert!(matches!(device.brand, Some(Cow::Borrowed("Samsung"))));
+    assert!(device.model.is_some());
+
+    let owned = utils::DD.parse(ua, None)?;
+    assert_eq!(detection.to_owned().to_value(), owned.clone().to_value());
+    assert_eq!(
+        serde_json::to_value(known)?,
+        serde_json::to_value(owned.get_known_device().unwrap())?
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_parse_ref_cached() -> Result<()> {
+    // taken from the cache, everything is owned, but otherwise the same.
+    let dd = DeviceDetector::builder()
+        .detection_cache(Arc::new(ShardedCache::new(10, 1)))
+        .build()?;
+    let ua = "Mozilla/5.0 (iPhone; CPU iPhone OS 17_0 like Mac OS X) AppleWebKit/605.1.15 (KHTML, like Gecko) Version/17.0 Mobile/15E148 Safari/604.1";
+
+    let first = dd.parse_ref(ua, None)?;
+    let second = dd.parse_ref(ua, None)?;
+    assert_eq!(dd.cache_stats().hits, 1);
+
+    let client = second.get_known_device().unwrap().client.as_ref().unwrap();
+    assert!(matches!(client.name, Cow::Owned(_)));
+    assert_eq!(first.to_value(), second.to_value());
+
+    Ok(())
+}
@@ -0,0 +1,172 @@
+use anyhow::Result;
+
+use std::io::Write;
+use std::sync::Arc;
+
+use rust_device_detector::cache::{DetectionCache, DiskCache, ShardedCache};
+use rust_device_detector::device_detector::DeviceDetector;
+
+use crate::utils;
+
+#[cfg(feature = "cache")]
+#[test]
+fn test_cache_key_includes_client_hints() -> Result<()> {
+    let dd = DeviceDetector::new_with_cache(100);
+    let ua = "Mozilla/5.0 (Linux; Android 10; K) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/120.0.0.0 Mobile Safari/537.36";
+
+    let model = |model: &str, other: &str| -> Result<Option<String>> {
+        let headers = vec![
+            (other.to_owned(), "anything".to_owned()),
+            ("Sec-CH-UA-Model".to_owned(), format!("\"{}\"", model)),
+        ];
+        Ok(dd
+            .parse(ua, Some(headers))?
+            .get_known_device()
+            .and_then(|x| x.device.as_ref())
+            .and_then(|x| x.model.clone()))
+    };
+
+    assert_eq!(model("Pixel 7", "Accept")?, Some("Pixel 7".to_owned()));
+    assert_eq!(model("Pixel 8", "Accept")?, Some("Pixel 8".to_owned()));
+    assert_eq!(dd.cache_stats().hits, 0);
+
+    // headers that make no difference don't miss the cache
+    assert_eq!(
+        model("Pixel 7", "Accept-Language")?,
+        Some("Pixel 7".to_owned())
+    );
+    assert_eq!(dd.cache_stats().hits, 1);
+
+    let none = dd.parse(ua, None)?;
+    assert_eq!(
+        none.get_known_device()
+            .and_then(|x| x.device.as_ref())
+            .and_then(|x| x.model.as_deref()),
+        None
+    );
+    assert_eq!(dd.cache_stats().misses, 3);
+
+    Ok(())
+}
+
+#[test]
+fn test_sharded_cache() -> Result<()> {
+    let cache = Arc::new(ShardedCache::new(2, 1));
+    let dd = DeviceDetector::builder()
+        .detection_cache(cache.clone())
+        .build()?;
+
+    let uas = ["curl/8.0", "Wget/1.21", "Googlebot/2.1"];
+    for ua in uas {
+        dd.parse(ua, None)?;
+    }
+    // full, so one of them made way
+    assert_eq!(dd.cache_stats().entries, 2);
+    assert_eq!(dd.cache_stats().misses, 3);
+
+    for ua in uas {
+        assert_eq!(
+            dd.parse(ua, None)?.to_value(),
+            utils::DD.parse(ua, None)?.to_value()
+        );
+    }
+    assert!(dd.cache_stats().hits >= 1);
+
+    // an empty one holds just as many
+    let empty = cache.empty();
+    assert_eq!(empty.entries(), 0);
+    let other = DeviceDetector::builder().detection_cache(empty).build()?;
+    for ua in uas {
+        other.parse(ua, None)?;
+    }
+    assert_eq!(other.cache_stats().entries, 2);
+    assert_eq!(dd.cache_stats().entries, 2);
+
+    dd.clear_cache();
+    assert_eq!(dd.cache_stats().entries, 0);
+
+    Ok(())
+}
+
+#[cfg(feature = "cache")]
+#[test]
+fn test_moka_cache_empty() -> Result<()> {
+    use rust_device_detector::cache::MokaCache;
+
+    let cache = Arc::new(MokaCache::builder().max_entries(100).build());
+    let dd = DeviceDetector::builder()
+        .detection_cache(cache.clone())
+        .build()?;
+    dd.parse("curl/8.0", None)?;
+    dd.parse("curl/8.0", None)?;
+    assert_eq!(dd.cache_stats().hits, 1);
+
+    // nothing the first one cached is in an empty one
+    let other = DeviceDetector::builder()
+        .detection_cache(cache.empty())
+        .build()?;
+    other.parse("curl/8.0", None)?;
+    assert_eq!(other.cache_stats().hits, 0);
+    assert_eq!(other.cache_stats().misses, 1);
+
+    Ok(())
+}
+
+#[test]
+fn test_disk_cache() -> Result<()> {
+    let dir = utils::ScratchDir::new("disk-cache");
+
+    let uas = [
+        "Googlebot/2.1",
+        "Mozilla/5.0 (Linux; Android 10; K) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/120.0.0.0 Mobile Safari/537.36",
+        "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/120.0.0.0 Safari/537.36",
+    ];
+    let headers = || {
+        Some(vec![(
+            "Sec-CH-UA-Model".to_owned(),
+            "\"Pixel 7\"".to_owned(),
+        )])
+    };
+
+    let dd = DeviceDetector::builder().disk_cache(&*dir).build()?;
+    for ua in uas {
+        dd.parse(ua, headers())?;
+    }
+    assert_eq!(dd.cache_stats().entries, 3);
+
+    // a second detector in the same process shares the open file
+    let shared = DeviceDetector::builder().disk_cache(&*dir).build()?;
+    assert_eq!(shared.cache_stats().entries, 3);
+    drop(shared);
+    drop(dd);
+
+    let path = DiskCache::open(&dir, utils::DD.database_version())?
+        .path()
+        .to_owned();
+    // as if the last write had been cut off
+    std::fs::OpenOptions::new()
+        .append(true)
+        .open(&path)?
+        .write_all(&[7, 0, 0])?;
+
+    let dd = DeviceDetector::builder().disk_cache(&*dir).build()?;
+    assert_eq!(dd.cache_stats().entries, 3);
+    for ua in uas {
+        assert_eq!(
+            dd.parse(ua, headers())?.to_value(),
+            utils::DD.parse(ua, headers())?.to_value()
+        );
+    }
+    assert_eq!(dd.cache_stats().hits, 3);
+    assert_eq!(dd.cache_stats().misses, 0);
+    drop(dd);
+
+    // other regexes start over, and the old file goes
+    let other = DiskCache::open(&dir, utils::DD.database_version() + 1)?;
+    assert_eq!(other.entries(), 0);
+    assert!(!path.exists());
+
+    drop(other);
+
+    Ok(())
+}
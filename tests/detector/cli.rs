@@ -0,0 +1,398 @@
+use anyhow::Result;
+
+use std::collections::BTreeMap;
+use std::io::Write;
+use std::process::{Command, Output, Stdio};
+
+// for running it as a server on a unix socket
+#[cfg(unix)]
+use std::{
+    io::Read,
+    os::unix::net::UnixStream,
+    path::Path,
+    process::{Child, ExitStatus},
+    time::{Duration, Instant},
+};
+
+use crate::utils;
+
+/// Runs the binary with `args`, feeding it `stdin`, and waits for it to exit.
+fn run(args: &[&str], stdin: &str) -> Result<Output> {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_rust-device-detector"))
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+    child
+        .stdin
+        .take()
+        .expect("piped stdin")
+        .write_all(stdin.as_bytes())?;
+    Ok(child.wait_with_output()?)
+}
+
+/// Each line the binary printed, parsed.
+fn lines(output: &Output) -> Result<Vec<serde_json::Value>> {
+    Ok(std::str::from_utf8(&output.stdout)?
+        .lines()
+        .map(serde_json::from_str)
+        .collect::<Result<_, _>>()?)
+}
+
+/// The binary, running until it exits or this is dropped.
+#[cfg(unix)]
+struct Running(Child);
+
+#[cfg(unix)]
+impl Running {
+    fn start(args: &[&str]) -> Result<Self> {
+        let child = Command::new(env!("CARGO_BIN_EXE_rust-device-detector"))
+            .args(args)
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()?;
+        Ok(Self(child))
+    }
+
+    /// Sends it SIGTERM, as a service manager would to stop it.
+    fn terminate(&self) -> Result<()> {
+        let status = Command::new("kill")
+            .arg("-TERM")
+            .arg(self.0.id().to_string())
+            .status()?;
+        anyhow::ensure!(status.success(), "kill failed");
+        Ok(())
+    }
+
+    /// Waits for it to exit, for at most `timeout`.
+    fn wait(&mut self, timeout: Duration) -> Result<ExitStatus> {
+        let started = Instant::now();
+        while started.elapsed() < timeout {
+            if let Some(status) = self.0.try_wait()? {
+                return Ok(status);
+            }
+            std::thread::sleep(Duration::from_millis(20));
+        }
+        anyhow::bail!("still running after {:?}", timeout)
+    }
+}
+
+#[cfg(unix)]
+impl Drop for Running {
+    fn drop(&mut self) {
+        let _ = self.0.kill();
+        let _ = self.0.wait();
+    }
+}
+
+/// Connects to the server on `path`, once it has started listening.
+#[cfg(unix)]
+fn connect(path: &Path) -> Result<UnixStream> {
+    // with the regexes to load first, this can take a while
+    for _ in 0..1000 {
+        if let Ok(stream) = UnixStream::connect(path) {
+            return Ok(stream);
+        }
+        std::thread::sleep(Duration::from_millis(20));
+    }
+    anyhow::bail!("nothing listening on {}", path.display())
+}
+
+#[test]
+fn test_cli_header() -> Result<()> {
+    let expected = utils::DD
+        .parse(utils::REDUCED_ANDROID, Some(utils::android_client_hints()))?
+        .to_value();
+    // the client hints have to make a difference for this to test anything
+    assert_ne!(
+        expected,
+        utils::DD.parse(utils::REDUCED_ANDROID, None)?.to_value()
+    );
+
+    let headers: Vec<String> = utils::android_client_hints()
+        .iter()
+        .map(|(name, value)| format!("{}: {}", name, value))
+        .collect();
+
+    // only the first colon of each separates the name from the value
+    let mut args = Vec::new();
+    for header in &headers {
+        args.extend(["-H", header]);
+    }
+    args.push(utils::REDUCED_ANDROID);
+    let output = run(&args, "")?;
+    assert!(output.status.success(), "{:?}", output);
+    assert_eq!(lines(&output)?, vec![expected.clone()]);
+
+    // or all at once on stdin, along with the user agent
+    let block = format!(
+        "User-Agent: {}\n{}\n",
+        utils::REDUCED_ANDROID,
+        headers.join("\n")
+    );
+    for flag in ["--headers", "--headers-file"] {
+        let output = run(&[flag, "-"], &block)?;
+        assert!(output.status.success(), "{}: {:?}", flag, output);
+        assert_eq!(lines(&output)?, vec![expected.clone()], "{}", flag);
+    }
+
+    Ok(())
+}
+
+/// A request for [`utils::REDUCED_ANDROID`] and its client hints, as json.
+fn android_request() -> serde_json::Value {
+    serde_json::json!({
+        "user_agent": utils::REDUCED_ANDROID,
+        "headers": utils::android_client_hints()
+            .into_iter()
+            .collect::<BTreeMap<_, _>>(),
+    })
+}
+
+#[test]
+fn test_cli_interactive_json() -> Result<()> {
+    let android = utils::DD
+        .parse(utils::REDUCED_ANDROID, Some(utils::android_client_hints()))?
+        .to_value();
+    let curl = utils::DD.parse("curl/8.0", None)?.to_value();
+    let curl_request = serde_json::json!({"user_agent": "curl/8.0"});
+
+    // json needn't be one request per line, and can come in arrays
+    let stdin = format!(
+        "{} [{}, {{\"headers\": {{}}}}]\n{:#}\n{{\"user_agent\": \n",
+        curl_request,
+        android_request(),
+        curl_request
+    );
+    let output = run(&["-i", "--input-format", "json"], &stdin)?;
+    let lines = lines(&output)?;
+    assert_eq!(lines.len(), 4, "{:?}", lines);
+    assert_eq!(lines[0], curl);
+    assert_eq!(lines[1], android);
+    assert_eq!(lines[2]["error"]["kind"], "invalid_request", "{}", lines[2]);
+    assert_eq!(lines[3], curl);
+    // there's no knowing where the next request would start after invalid
+    // json, so that is the end of it
+    assert!(!output.status.success(), "{:?}", output);
+
+    Ok(())
+}
+
+#[test]
+fn test_cli_interactive_ndjson() -> Result<()> {
+    let android = utils::DD
+        .parse(utils::REDUCED_ANDROID, Some(utils::android_client_hints()))?
+        .to_value();
+    let curl = utils::DD.parse("curl/8.0", None)?.to_value();
+    let curl_request = serde_json::json!({"user_agent": "curl/8.0"});
+
+    // with a request per line, only the one malformed line is lost
+    let stdin = format!(
+        "{}\n{{\"user_agent\": \n\n{}\n",
+        android_request(),
+        curl_request
+    );
+    let output = run(&["-i", "--input-format", "ndjson"], &stdin)?;
+    assert!(output.status.success(), "{:?}", output);
+    let lines = lines(&output)?;
+    assert_eq!(lines.len(), 3, "{:?}", lines);
+    assert_eq!(lines[0], android);
+    assert_eq!(lines[1]["error"]["kind"], "invalid_request", "{}", lines[1]);
+    assert_eq!(lines[2], curl);
+
+    Ok(())
+}
+
+#[test]
+fn test_cli_interactive_raw_headers() -> Result<()> {
+    let android = utils::DD
+        .parse(utils::REDUCED_ANDROID, Some(utils::android_client_hints()))?
+        .to_value();
+
+    let mut block = format!("User-Agent: {}\n", utils::REDUCED_ANDROID);
+    for (name, value) in utils::android_client_hints() {
+        block.push_str(&format!("{}: {}\n", name, value));
+    }
+
+    let stdin = format!(
+        "{}\nUser-Agent: curl/8.0\nnot a header\n\nAccept: */*\n\n\n{}",
+        block, block
+    );
+    let output = run(&["-i", "--input-format", "raw-headers"], &stdin)?;
+    assert!(output.status.success(), "{:?}", output);
+
+    // every block gets a line, even those that can't be detected
+    let lines = lines(&output)?;
+    assert_eq!(lines.len(), 4, "{:?}", lines);
+    assert_eq!(lines[0], android);
+    assert_eq!(lines[1]["error"]["kind"], "invalid_header", "{}", lines[1]);
+    assert_eq!(lines[2]["error"]["kind"], "invalid_request", "{}", lines[2]);
+    assert_eq!(lines[3], android);
+
+    Ok(())
+}
+
+/// `value` as framed mode reports it, tagged with `id`.
+fn tagged(mut value: serde_json::Value, id: serde_json::Value) -> serde_json::Value {
+    value["id"] = id;
+    value
+}
+
+#[test]
+fn test_cli_framed() -> Result<()> {
+    let with_headers = serde_json::json!({
+        "id": "android",
+        "ua": utils::REDUCED_ANDROID,
+        "headers": utils::android_client_hints()
+            .into_iter()
+            .collect::<BTreeMap<_, _>>(),
+    });
+    // spread over several lines, so it needs its length sent first
+    let with_headers = serde_json::to_string_pretty(&with_headers)?;
+
+    let stdin = format!(
+        "{}\n{}\n{}\n{{\"ua\": \"curl/8.0\"\n",
+        serde_json::json!({"ua": "curl/8.0", "id": 7}),
+        with_headers.len(),
+        with_headers,
+    );
+    let output = run(&["--framed"], &stdin)?;
+    assert!(output.status.success(), "{:?}", output);
+
+    let lines = lines(&output)?;
+    assert_eq!(lines.len(), 3, "{:?}", lines);
+    assert_eq!(
+        lines[0],
+        tagged(utils::DD.parse("curl/8.0", None)?.to_value(), 7.into())
+    );
+    assert_eq!(
+        lines[1],
+        tagged(
+            utils::DD
+                .parse(utils::REDUCED_ANDROID, Some(utils::android_client_hints()))?
+                .to_value(),
+            "android".into()
+        )
+    );
+    // the malformed one has no id of its own, so gets its position instead
+    assert_eq!(lines[2]["error"]["kind"], "invalid_request", "{}", lines[2]);
+    assert_eq!(lines[2]["id"], 3, "{}", lines[2]);
+
+    Ok(())
+}
+
+#[cfg(unix)]
+#[test]
+fn test_cli_unix_socket() -> Result<()> {
+    let dir = utils::ScratchDir::new("cli-unix-socket");
+    std::fs::create_dir_all(&*dir)?;
+    let socket = dir.join("rdd.sock");
+    let socket_arg = socket.to_str().expect("utf-8 path");
+
+    let _server = Running::start(&["--server", "--unix-socket", socket_arg])?;
+
+    let mut stream = connect(&socket)?;
+    stream.write_all(
+        b"POST /detect HTTP/1.1\r\nHost: test\r\nConnection: close\r\nContent-Length: 8\r\n\r\ncurl/8.0",
+    )?;
+    let mut response = String::new();
+    stream.read_to_string(&mut response)?;
+    assert!(response.starts_with("HTTP/1.1 200"), "{}", response);
+    assert!(response.contains("\"name\":\"curl\""), "{}", response);
+
+    // it can't be combined with a port
+    let output = run(
+        &["--server", "--unix-socket", socket_arg, "--port", "8081"],
+        "",
+    )?;
+    assert!(!output.status.success(), "{:?}", output);
+
+    Ok(())
+}
+
+#[cfg(unix)]
+#[test]
+fn test_cli_graceful_shutdown() -> Result<()> {
+    let dir = utils::ScratchDir::new("cli-graceful-shutdown");
+    std::fs::create_dir_all(&*dir)?;
+    let socket = dir.join("rdd.sock");
+    let socket_arg = socket.to_str().expect("utf-8 path");
+
+    let mut server = Running::start(&[
+        "--server",
+        "--unix-socket",
+        socket_arg,
+        "--shutdown-timeout",
+        "10",
+    ])?;
+
+    let mut stream = connect(&socket)?;
+    stream.write_all(
+        b"POST /detect HTTP/1.1\r\nHost: test\r\nConnection: close\r\nContent-Length: 8\r\n\r\ncurl",
+    )?;
+    // give the server a moment to start on it
+    std::thread::sleep(Duration::from_millis(200));
+
+    let terminated = Instant::now();
+    server.terminate()?;
+
+    // no new connections are taken from then on
+    while socket.exists() && terminated.elapsed() < Duration::from_secs(10) {
+        std::thread::sleep(Duration::from_millis(20));
+    }
+    assert!(!socket.exists(), "the socket was never removed");
+    assert!(UnixStream::connect(&socket).is_err());
+
+    // but the request in flight is still answered
+    stream.write_all(b"/8.0")?;
+    let mut response = String::new();
+    stream.read_to_string(&mut response)?;
+    assert!(response.starts_with("HTTP/1.1 200"), "{}", response);
+    assert!(response.contains("\"name\":\"curl\""), "{}", response);
+
+    // after which there is nothing left to wait for
+    let status = server.wait(Duration::from_secs(10))?;
+    assert!(status.success(), "{}", status);
+
+    Ok(())
+}
+
+#[cfg(unix)]
+#[test]
+fn test_cli_shutdown_timeout() -> Result<()> {
+    let dir = utils::ScratchDir::new("cli-shutdown-timeout");
+    std::fs::create_dir_all(&*dir)?;
+    let socket = dir.join("rdd.sock");
+    let socket_arg = socket.to_str().expect("utf-8 path");
+
+    let mut server = Running::start(&[
+        "--server",
+        "--unix-socket",
+        socket_arg,
+        "--shutdown-timeout",
+        "1",
+    ])?;
+
+    // a request that is never finished doesn't hold up shutting down for
+    // longer than the timeout
+    let mut stream = connect(&socket)?;
+    stream.write_all(
+        b"POST /detect HTTP/1.1\r\nHost: test\r\nConnection: close\r\nContent-Length: 8\r\n\r\ncurl",
+    )?;
+    std::thread::sleep(Duration::from_millis(200));
+
+    let terminated = Instant::now();
+    server.terminate()?;
+    let status = server.wait(Duration::from_secs(10))?;
+    assert!(status.success(), "{}", status);
+    assert!(
+        terminated.elapsed() < Duration::from_secs(5),
+        "took {:?}",
+        terminated.elapsed()
+    );
+    assert!(!socket.exists());
+
+    Ok(())
+}
@@ -0,0 +1,49 @@
+use anyhow::Result;
+
+use rust_device_detector::device_detector::DeviceDetector;
+
+use crate::utils;
+
+#[test]
+fn test_regex_stats() -> Result<()> {
+    // 'Daum(?!(?:Apps|Device))' in bots.yml has a look ahead, which only
+    // fancy_regex can do, but most of what gets compiled along the way
+    // doesn't need it.
+    let detection = utils::DD.parse("Daum/4.1", None)?;
+    assert_eq!(
+        detection.get_bot().map(|bot| bot.name.as_str()),
+        Some("Daum")
+    );
+
+    let stats = DeviceDetector::regex_stats();
+    assert!(stats.fancy > 0, "{:?}", stats);
+    assert!(stats.plain > stats.fancy, "{:?}", stats);
+
+    Ok(())
+}
+
+#[test]
+fn test_warm_up() -> Result<()> {
+    // a detector of its own, as others' regexes are compiled as tests run
+    let dd = DeviceDetector::from_regex_dir("regexes")?;
+
+    let before = DeviceDetector::regex_stats();
+    let warm_up = dd.warm_up(2)?;
+    let after = DeviceDetector::regex_stats();
+
+    assert!(warm_up.patterns > 10000, "{:?}", warm_up);
+    assert_eq!(warm_up.compiled, warm_up.patterns);
+    assert!(
+        (after.plain + after.fancy) - (before.plain + before.fancy) >= warm_up.compiled,
+        "{:?} {:?}",
+        before,
+        after
+    );
+
+    // nothing left to compile the second time around
+    let again = dd.warm_up(2)?;
+    assert_eq!(again.patterns, warm_up.patterns);
+    assert_eq!(again.compiled, 0);
+
+    Ok(())
+}
@@ -0,0 +1,43 @@
+use anyhow::Result;
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use rust_device_detector::device_detector::DeviceDetector;
+use rust_device_detector::diagnostics::Diagnostics;
+
+use crate::utils;
+
+#[test]
+fn test_backtrack_limit_is_recorded() -> Result<()> {
+    // the look ahead forces fancy_regex to backtrack through the nested
+    // repetition itself, which takes exponential time without a match.
+    let dir = utils::regexes_with_bot("backtrack", "((?=a)a+)+$", "Backtrack Test")?;
+
+    let calls = Arc::new(AtomicUsize::new(0));
+    let diagnostics = Diagnostics::with_callback({
+        let calls = calls.clone();
+        move |failure| {
+            assert_eq!(failure.list, "bots.yml");
+            calls.fetch_add(1, Ordering::SeqCst);
+        }
+    });
+
+    let dd = DeviceDetector::builder()
+        .regex_dir(&*dir)
+        .diagnostics(Arc::new(diagnostics))
+        .build()?;
+
+    let ua = format!("{}!", "a".repeat(30));
+    assert!(!dd.parse(&ua, None)?.is_bot());
+
+    let failures = dd.diagnostics().regex_failures();
+    assert_eq!(failures.len(), 1, "{:?}", failures);
+    assert_eq!(failures[0].list, "bots.yml");
+    assert!(failures[0].pattern.contains("((?=a)a+)+$"));
+    assert_eq!(failures[0].count, 1);
+    assert_eq!(failures[0].user_agent, ua);
+    assert_eq!(calls.load(Ordering::SeqCst), 1);
+
+    Ok(())
+}
@@ -0,0 +1,34 @@
+use anyhow::Result;
+
+use rust_device_detector::device_detector::DeviceDetector;
+use rust_device_detector::error::Error;
+
+use crate::utils;
+
+#[test]
+fn test_invalid_regex_error() -> Result<()> {
+    // neither regex engine can look behind a variable length
+    let dir = utils::regexes_with_bot(
+        "invalid-regex",
+        "(?<=a+)InvalidRegexAgent",
+        "Invalid Regex Agent",
+    )?;
+
+    // the regexes are only compiled when first needed
+    let dd = DeviceDetector::from_regex_dir(&*dir)?;
+
+    let err = dd
+        .parse("InvalidRegexAgent/1.0", None)
+        .expect_err("invalid regex should fail");
+
+    assert_eq!(err.kind(), "invalid_regex");
+    match &err {
+        Error::InvalidRegex { pattern, .. } => {
+            assert!(pattern.contains("(?<=a+)InvalidRegexAgent"), "{}", pattern)
+        }
+        err => panic!("unexpected error: {:?}", err),
+    }
+    assert_eq!(err.to_value()["error"]["kind"], "invalid_regex");
+
+    Ok(())
+}
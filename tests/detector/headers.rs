@@ -0,0 +1,32 @@
+use anyhow::Result;
+
+use rust_device_detector::client_hints::parse_header_block;
+
+use crate::utils;
+
+#[test]
+fn test_parse_header_block() -> Result<()> {
+    let block = "GET /detect HTTP/1.1\r\nUser-Agent: Mozilla/5.0 (Linux; Android 10; K) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/120.0.0.0 Mobile Safari/537.36\r\nX-Requested-With: https://example.com\r\nSec-CH-UA-Model: \"Pixel 7\"\r\nSec-CH-UA-Full-Version-List: \"Chromium\";v=\"120.0.6099.43\",\r\n \"Google Chrome\";v=\"120.0.6099.43\"\r\n";
+
+    let headers = parse_header_block(block)?;
+    assert_eq!(headers.len(), 4);
+    assert_eq!(headers[1].0, "X-Requested-With");
+    // only the first colon separates the name
+    assert_eq!(headers[1].1, "https://example.com");
+    assert_eq!(
+        headers[3].1,
+        "\"Chromium\";v=\"120.0.6099.43\", \"Google Chrome\";v=\"120.0.6099.43\""
+    );
+
+    let detection = utils::DD.parse(&headers[0].1, Some(headers.clone()))?;
+    let device = detection
+        .get_known_device()
+        .and_then(|x| x.device.as_ref())
+        .expect("device");
+    assert_eq!(device.model.as_deref(), Some("Pixel 7"));
+
+    let err = parse_header_block("User-Agent: curl/8.0\nnot a header\n").expect_err("no colon");
+    assert_eq!(err.kind(), "invalid_header");
+
+    Ok(())
+}
@@ -0,0 +1,743 @@
+use anyhow::Result;
+
+use std::collections::BTreeMap;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+#[cfg(unix)]
+use tokio::net::UnixStream;
+
+use rust_device_detector::cache::{DetectionCache, DiskCache, ShardedCache};
+use rust_device_detector::device_detector::{DeviceDetector, DeviceDetectorBuilder};
+use rust_device_detector::http::{server_with_options, Limits, Listen, Options, Reload};
+use rust_device_detector::options::DetectionOptions;
+
+use crate::utils;
+
+/// Starts a server on a free port of its own, which runs until the test's
+/// runtime is dropped.
+async fn start(options: Options) -> Result<SocketAddr> {
+    start_with(utils::DD.clone(), options).await
+}
+
+async fn start_with(detector: DeviceDetector, options: Options) -> Result<SocketAddr> {
+    let addr = std::net::TcpListener::bind("127.0.0.1:0")?.local_addr()?;
+    tokio::spawn(server_with_options(addr, detector, options));
+
+    for _ in 0..100 {
+        if TcpStream::connect(addr).await.is_ok() {
+            return Ok(addr);
+        }
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+    anyhow::bail!("the server never started listening on {}", addr)
+}
+
+fn reload(builder: DeviceDetectorBuilder, token: Option<&str>) -> Reload {
+    Reload {
+        builder,
+        watch_interval: None,
+        smoke_corpus: Vec::new(),
+        warm_up_threads: None,
+        token: token.map(str::to_owned),
+    }
+}
+
+fn limits(limits: Limits) -> Options {
+    Options {
+        limits,
+        ..Options::default()
+    }
+}
+
+/// Sends `request` as it is, and reads until the server closes the
+/// connection.
+async fn send(addr: SocketAddr, request: &str) -> Result<String> {
+    send_bytes(addr, request.as_bytes()).await
+}
+
+async fn send_bytes(addr: SocketAddr, request: &[u8]) -> Result<String> {
+    let mut stream = TcpStream::connect(addr).await?;
+    stream.write_all(request).await?;
+    let mut response = String::new();
+    stream.read_to_string(&mut response).await?;
+    Ok(response)
+}
+
+fn post(path: &str, body: &str) -> String {
+    format!(
+        "POST {} HTTP/1.1\r\nHost: test\r\nConnection: close\r\nContent-Length: {}\r\n\r\n{}",
+        path,
+        body.len(),
+        body
+    )
+}
+
+fn post_json(path: &str, body: &serde_json::Value) -> String {
+    let body = body.to_string();
+    format!(
+        "POST {} HTTP/1.1\r\nHost: test\r\nConnection: close\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\r\n{}",
+        path,
+        body.len(),
+        body
+    )
+}
+
+fn get(path: &str) -> String {
+    format!(
+        "GET {} HTTP/1.1\r\nHost: test\r\nConnection: close\r\n\r\n",
+        path
+    )
+}
+
+fn post_reload(token: &str) -> String {
+    format!(
+        "POST /reload HTTP/1.1\r\nHost: test\r\nConnection: close\r\nAuthorization: Bearer {}\r\nContent-Length: 0\r\n\r\n",
+        token
+    )
+}
+
+/// Detects `ua` over and over, until the bot it is detected as is `bot`.
+async fn wait_for_bot(addr: SocketAddr, ua: &str, bot: &str) -> Result<()> {
+    let mut response = String::new();
+    for _ in 0..500 {
+        response = send(addr, &post("/detect", ua)).await?;
+        if response.contains(&format!("\"name\":\"{}\"", bot)) {
+            return Ok(());
+        }
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+    anyhow::bail!("{} was never detected as {}: {}", ua, bot, response)
+}
+
+/// A request that never finishes sending its body, so it is held for as long
+/// as the server lets it.
+async fn stalled(addr: SocketAddr) -> Result<TcpStream> {
+    let mut stream = TcpStream::connect(addr).await?;
+    stream
+        .write_all(b"POST /detect HTTP/1.1\r\nHost: test\r\nContent-Length: 100\r\n\r\ncurl")
+        .await?;
+    Ok(stream)
+}
+
+fn status(response: &str) -> &str {
+    response.split(' ').nth(1).unwrap_or_default()
+}
+
+/// The value of the header `name` in `response`, if it has one.
+fn header<'a>(response: &'a str, name: &str) -> Option<&'a str> {
+    let (head, _) = response.split_once("\r\n\r\n")?;
+    head.lines().skip(1).find_map(|line| {
+        let (header, value) = line.split_once(':')?;
+        header.eq_ignore_ascii_case(name).then(|| value.trim())
+    })
+}
+
+/// The body of a response that isn't chunked, parsed.
+fn body(response: &str) -> Result<serde_json::Value> {
+    let (_, body) = response
+        .split_once("\r\n\r\n")
+        .ok_or_else(|| anyhow::anyhow!("no body in {}", response))?;
+    Ok(serde_json::from_str(body)?)
+}
+
+/// The body of a response sent with chunked transfer encoding, as batches
+/// are, put back together.
+fn dechunk(response: &str) -> Result<String> {
+    let (_, mut rest) = response
+        .split_once("\r\n\r\n")
+        .ok_or_else(|| anyhow::anyhow!("no body in {}", response))?;
+
+    let mut body = String::new();
+    loop {
+        let (size, after) = rest
+            .split_once("\r\n")
+            .ok_or_else(|| anyhow::anyhow!("unterminated chunk in {}", response))?;
+        let size = usize::from_str_radix(size, 16)?;
+        if size == 0 {
+            return Ok(body);
+        }
+        body.push_str(&after[..size]);
+        rest = &after[size + 2..];
+    }
+}
+
+/// Each line of a batch response, parsed.
+fn batch_lines(body: &str) -> Result<Vec<serde_json::Value>> {
+    Ok(body
+        .lines()
+        .map(serde_json::from_str)
+        .collect::<Result<_, _>>()?)
+}
+
+fn detected(ua: &str) -> Result<serde_json::Value> {
+    Ok(utils::DD.parse(ua, None)?.to_value())
+}
+
+fn error_kind(line: &serde_json::Value) -> Option<&str> {
+    line["error"]["kind"].as_str()
+}
+
+#[tokio::test]
+async fn test_http_detect() -> Result<()> {
+    let addr = start(Options::default()).await?;
+
+    let response = send(addr, &post("/detect", "curl/8.0")).await?;
+    assert_eq!(status(&response), "200", "{}", response);
+    assert!(response.contains("\"name\":\"curl\""), "{}", response);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_http_detect_client_hints() -> Result<()> {
+    let addr = start(Options::default()).await?;
+
+    let expected = utils::DD
+        .parse(utils::REDUCED_ANDROID, Some(utils::android_client_hints()))?
+        .to_value();
+
+    let request = serde_json::json!({
+        "user_agent": utils::REDUCED_ANDROID,
+        "headers": utils::android_client_hints()
+            .into_iter()
+            .collect::<BTreeMap<_, _>>(),
+    });
+    let response = send(addr, &post_json("/detect", &request)).await?;
+    assert_eq!(status(&response), "200", "{}", response);
+    assert_eq!(body(&response)?, expected);
+
+    // a browser sent here directly has its own client hints detected
+    let mut request = format!(
+        "GET /detect HTTP/1.1\r\nHost: test\r\nConnection: close\r\nUser-Agent: {}\r\n",
+        utils::REDUCED_ANDROID
+    );
+    for (name, value) in utils::android_client_hints() {
+        request.push_str(&format!("{}: {}\r\n", name, value));
+    }
+    request.push_str("\r\n");
+    let response = send(addr, &request).await?;
+    assert_eq!(status(&response), "200", "{}", response);
+    assert_eq!(body(&response)?, expected);
+
+    // and is asked for the ones it didn't send yet
+    let accept_ch = header(&response, "accept-ch").unwrap_or_default();
+    for hint in [
+        "Sec-CH-UA-Model",
+        "Sec-CH-UA-Platform-Version",
+        "Sec-CH-UA-Full-Version-List",
+    ] {
+        assert!(
+            accept_ch.split(", ").any(|x| x == hint),
+            "{}: {}",
+            hint,
+            response
+        );
+    }
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_http_metrics() -> Result<()> {
+    let detector = DeviceDetector::builder()
+        .detection_cache(Arc::new(ShardedCache::new(100, 4)))
+        .build()?;
+    let addr = start_with(detector, Options::default()).await?;
+
+    // the second curl is answered from the cache
+    for ua in [
+        "curl/8.0",
+        "curl/8.0",
+        "Googlebot/2.1 (+http://www.google.com/bot.html)",
+    ] {
+        let response = send(addr, &post("/detect", ua)).await?;
+        assert_eq!(status(&response), "200", "{}", response);
+    }
+    let response = send(addr, &get("/nowhere")).await?;
+    assert_eq!(status(&response), "404", "{}", response);
+
+    let response = send(addr, &get("/metrics")).await?;
+    assert_eq!(status(&response), "200", "{}", response);
+    let (_, metrics) = response.split_once("\r\n\r\n").unwrap_or_default();
+
+    for line in [
+        "rdd_http_requests_total{route=\"/detect\",status=\"200\"} 3",
+        "rdd_http_requests_total{route=\"other\",status=\"404\"} 1",
+        "rdd_cache_hits_total 1",
+        "rdd_cache_misses_total 2",
+        "rdd_detections_total{kind=\"bot\"} 1",
+        "rdd_detections_total{kind=\"known\"} 2",
+        "rdd_detections_by_client_type_total{client_type=\"library\"} 2",
+    ] {
+        assert!(metrics.lines().any(|x| x == line), "{}:\n{}", line, metrics);
+    }
+
+    Ok(())
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn test_http_unix_socket() -> Result<()> {
+    let dir = utils::ScratchDir::new("http-unix-socket");
+    std::fs::create_dir_all(&*dir)?;
+    let path = dir.join("rdd.sock");
+
+    let listen = Listen::Unix(path.clone());
+    tokio::spawn(server_with_options(
+        listen,
+        utils::DD.clone(),
+        Options::default(),
+    ));
+
+    let mut stream = None;
+    for _ in 0..100 {
+        if let Ok(connected) = UnixStream::connect(&path).await {
+            stream = Some(connected);
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+    let mut stream = stream.ok_or_else(|| anyhow::anyhow!("nothing listening on {:?}", path))?;
+
+    stream
+        .write_all(post("/detect", "curl/8.0").as_bytes())
+        .await?;
+    let mut response = String::new();
+    stream.read_to_string(&mut response).await?;
+    assert_eq!(status(&response), "200", "{}", response);
+    assert!(response.contains("\"name\":\"curl\""), "{}", response);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_http_version_truncation() -> Result<()> {
+    let addr = start(Options::default()).await?;
+
+    let ua = "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/120.0.6099.71 Safari/537.36";
+    for truncation in ["none", "major", "minor", "patch", "build"] {
+        let detector = utils::DD.with_options(DetectionOptions {
+            version_truncation: truncation.parse().map_err(anyhow::Error::msg)?,
+            ..DetectionOptions::default()
+        });
+        let expected = detector.parse(ua, None)?.to_value();
+
+        let path = format!("/detect?version_truncation={}", truncation);
+        let response = send(addr, &post(&path, ua)).await?;
+        assert_eq!(status(&response), "200", "{}", response);
+        assert_eq!(body(&response)?, expected, "{}", truncation);
+    }
+
+    // the truncation makes a difference, rather than being ignored
+    let response = send(addr, &post("/detect?version_truncation=major", ua)).await?;
+    assert!(response.contains("\"version\":\"120\""), "{}", response);
+    let response = send(addr, &post("/detect?version_truncation=none", ua)).await?;
+    assert!(
+        response.contains("\"version\":\"120.0.6099.71\""),
+        "{}",
+        response
+    );
+
+    let response = send(addr, &post("/detect?version_truncation=most", ua)).await?;
+    assert_eq!(status(&response), "400", "{}", response);
+    assert_eq!(error_kind(&body(&response)?), Some("invalid_request"));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_http_invalid_utf8() -> Result<()> {
+    let addr = start(Options::default()).await?;
+
+    let mut request =
+        b"POST /detect HTTP/1.1\r\nHost: test\r\nConnection: close\r\nContent-Length: 6\r\n\r\ncurl/"
+            .to_vec();
+    request.push(0xff);
+    let response = send_bytes(addr, &request).await?;
+    assert_eq!(status(&response), "422", "{}", response);
+    assert_eq!(
+        header(&response, "content-type"),
+        Some("application/json"),
+        "{}",
+        response
+    );
+
+    let error = body(&response)?;
+    assert_eq!(error_kind(&error), Some("invalid_utf8"), "{}", response);
+    assert!(error["error"]["message"].is_string(), "{}", response);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_http_payload_too_large() -> Result<()> {
+    let addr = start(limits(Limits {
+        max_body_bytes: 16,
+        ..Limits::default()
+    }))
+    .await?;
+
+    let response = send(addr, &post("/detect", &"x".repeat(17))).await?;
+    assert_eq!(status(&response), "413", "{}", response);
+    assert!(response.contains("payload_too_large"), "{}", response);
+
+    // right at the limit is fine
+    let response = send(addr, &post("/detect", &"x".repeat(16))).await?;
+    assert_eq!(status(&response), "200", "{}", response);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_http_request_timeout() -> Result<()> {
+    let addr = start(limits(Limits {
+        request_timeout: Duration::from_millis(100),
+        ..Limits::default()
+    }))
+    .await?;
+
+    let mut stream = stalled(addr).await?;
+    let mut response = String::new();
+    tokio::time::timeout(
+        Duration::from_secs(10),
+        stream.read_to_string(&mut response),
+    )
+    .await??;
+    assert_eq!(status(&response), "408", "{}", response);
+    assert!(response.contains("\"kind\":\"timeout\""), "{}", response);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_http_too_many_requests() -> Result<()> {
+    let addr = start(limits(Limits {
+        max_concurrent_requests: 1,
+        ..Limits::default()
+    }))
+    .await?;
+
+    let held = stalled(addr).await?;
+    // give the server a moment to start on it
+    tokio::time::sleep(Duration::from_millis(200)).await;
+
+    let response = send(addr, &post("/detect", "curl/8.0")).await?;
+    assert_eq!(status(&response), "503", "{}", response);
+    assert!(response.contains("too_many_requests"), "{}", response);
+
+    // health checks don't count
+    let response = send(
+        addr,
+        "GET /health HTTP/1.1\r\nHost: test\r\nConnection: close\r\n\r\n",
+    )
+    .await?;
+    assert_eq!(status(&response), "200", "{}", response);
+
+    // and once the first request is gone, there is room again
+    drop(held);
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    let response = send(addr, &post("/detect", "curl/8.0")).await?;
+    assert_eq!(status(&response), "200", "{}", response);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_http_header_read_timeout() -> Result<()> {
+    let addr = start(limits(Limits {
+        header_read_timeout: Duration::from_millis(200),
+        ..Limits::default()
+    }))
+    .await?;
+
+    let mut stream = TcpStream::connect(addr).await?;
+    stream
+        .write_all(b"GET /health HTTP/1.1\r\nHost: te")
+        .await?;
+
+    // the connection is closed on us, without a response
+    let mut response = String::new();
+    tokio::time::timeout(
+        Duration::from_secs(10),
+        stream.read_to_string(&mut response),
+    )
+    .await??;
+    assert!(!response.contains("OK"), "{}", response);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_http_reload_is_not_limited() -> Result<()> {
+    let addr = start(Options {
+        reload: Some(reload(DeviceDetector::builder(), Some("secret"))),
+        limits: Limits {
+            // far less than it takes to load the regexes
+            request_timeout: Duration::from_millis(1),
+            max_concurrent_requests: 1,
+            ..Limits::default()
+        },
+        ..Options::default()
+    })
+    .await?;
+
+    let _held = stalled(addr).await?;
+    tokio::time::sleep(Duration::from_millis(200)).await;
+
+    let response = send(addr, &post_reload("secret")).await?;
+    assert_eq!(status(&response), "200", "{}", response);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_http_reload_needs_token() -> Result<()> {
+    // without a token, there is no such route
+    let addr = start(Options {
+        reload: Some(reload(DeviceDetector::builder(), None)),
+        ..Options::default()
+    })
+    .await?;
+    let response = send(addr, &post("/reload", "")).await?;
+    assert_eq!(status(&response), "404", "{}", response);
+
+    let addr = start(Options {
+        reload: Some(reload(DeviceDetector::builder(), Some("secret"))),
+        ..Options::default()
+    })
+    .await?;
+
+    let response = send(addr, &post("/reload", "")).await?;
+    assert_eq!(status(&response), "401", "{}", response);
+
+    for wrong in ["secreT", "secre", "secrets", ""] {
+        let response = send(addr, &post_reload(wrong)).await?;
+        assert_eq!(status(&response), "401", "{}: {}", wrong, response);
+    }
+
+    let response = send(addr, &post_reload("secret")).await?;
+    assert_eq!(status(&response), "200", "{}", response);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_http_reload() -> Result<()> {
+    let dir = utils::regexes_with_bot("http-reload", "HttpReloadBot", "Before Reload")?;
+    let cache = Arc::new(ShardedCache::new(100, 4));
+    let builder = DeviceDetector::builder()
+        .regex_dir(&*dir)
+        .detection_cache(cache.clone());
+
+    let addr = start_with(
+        builder.clone().build()?,
+        Options {
+            reload: Some(reload(builder, Some("secret"))),
+            ..Options::default()
+        },
+    )
+    .await?;
+
+    wait_for_bot(addr, "HttpReloadBot/1.0", "Before Reload").await?;
+    assert_eq!(cache.entries(), 1);
+
+    utils::prepend_bot(&dir, "HttpReloadBot", "After Reload")?;
+    let response = send(addr, &post_reload("secret")).await?;
+    assert_eq!(status(&response), "200", "{}", response);
+    wait_for_bot(addr, "HttpReloadBot/1.0", "After Reload").await?;
+
+    // the new regexes got a cache of their own, rather than sharing one with
+    // detections made by the old ones
+    assert_eq!(cache.entries(), 0);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_http_reload_disk_cache() -> Result<()> {
+    let dir = utils::ScratchDir::new("http-reload-disk-cache");
+    let builder = DeviceDetector::builder().disk_cache(&*dir);
+    let detector = builder.clone().build()?;
+    let cache = DiskCache::open(&*dir, detector.database_version())?;
+
+    let addr = start_with(
+        detector,
+        Options {
+            reload: Some(reload(builder, Some("secret"))),
+            ..Options::default()
+        },
+    )
+    .await?;
+
+    let response = send(addr, &post("/detect", "curl/8.0")).await?;
+    assert_eq!(status(&response), "200", "{}", response);
+    assert_eq!(cache.entries(), 1);
+
+    // the regexes are the same, so the new detector opens the same file, and
+    // the old detector going away mustn't empty it
+    let response = send(addr, &post_reload("secret")).await?;
+    assert_eq!(status(&response), "200", "{}", response);
+    assert!(cache.entries() > 1, "{:?}", cache);
+
+    let response = send(addr, &post("/detect", "curl/8.0")).await?;
+    assert!(response.contains("\"name\":\"curl\""), "{}", response);
+    let metrics = send(addr, &get("/metrics")).await?;
+    assert!(
+        metrics.contains("\nrdd_cache_hits_total 1\n"),
+        "{}",
+        metrics
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_http_reload_on_watch() -> Result<()> {
+    let dir = utils::regexes_with_bot("http-watch", "HttpWatchBot", "Before Watch")?;
+    let builder = DeviceDetector::builder().regex_dir(&*dir);
+
+    let addr = start_with(
+        builder.clone().build()?,
+        Options {
+            reload: Some(Reload {
+                watch_interval: Some(Duration::from_millis(50)),
+                ..reload(builder, None)
+            }),
+            ..Options::default()
+        },
+    )
+    .await?;
+
+    wait_for_bot(addr, "HttpWatchBot/1.0", "Before Watch").await?;
+
+    // mtimes can be coarse, but the size changes too
+    utils::prepend_bot(&dir, "HttpWatchBot", "After Watch")?;
+    wait_for_bot(addr, "HttpWatchBot/1.0", "After Watch").await?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_http_batch_order() -> Result<()> {
+    let addr = start(Options::default()).await?;
+
+    // enough, and different enough, for the threads to finish out of order
+    let uas: Vec<String> = (0..200)
+        .map(|i| match i % 3 {
+            0 => format!("curl/{}.0", i),
+            1 => format!("Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/{}.0.0.0 Safari/537.36", i),
+            _ => format!("Mozilla/5.0 (Linux; Android 13; SM-S911B) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/{}.0.0.0 Mobile Safari/537.36", i),
+        })
+        .collect();
+
+    let request = format!(
+        "POST /detect/batch HTTP/1.1\r\nHost: test\r\nConnection: close\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\r\n{}",
+        serde_json::to_string(&uas)?.len(),
+        serde_json::to_string(&uas)?
+    );
+    let response = send(addr, &request).await?;
+    assert_eq!(status(&response), "200", "{}", response);
+
+    let lines = batch_lines(&dechunk(&response)?)?;
+    assert_eq!(lines.len(), uas.len());
+    for (ua, line) in uas.iter().zip(lines) {
+        assert_eq!(line, detected(ua)?, "ua: {}", ua);
+    }
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_http_batch_errors() -> Result<()> {
+    let addr = start(Options::default()).await?;
+
+    let items = serde_json::json!([
+        "curl/8.0",
+        42,
+        {"user_agent": "Wget/1.21", "headers": {}},
+        {"headers": {}},
+        "Googlebot/2.1",
+    ]);
+    let request = format!(
+        "POST /detect/batch HTTP/1.1\r\nHost: test\r\nConnection: close\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\r\n{}",
+        items.to_string().len(),
+        items
+    );
+    let response = send(addr, &request).await?;
+    assert_eq!(status(&response), "200", "{}", response);
+
+    // the items that aren't valid get an error each, and the rest are
+    // detected all the same
+    let lines = batch_lines(&dechunk(&response)?)?;
+    assert_eq!(lines.len(), 5, "{:?}", lines);
+    assert_eq!(lines[0], detected("curl/8.0")?);
+    assert_eq!(error_kind(&lines[1]), Some("invalid_request"));
+    assert_eq!(lines[2], detected("Wget/1.21")?);
+    assert_eq!(error_kind(&lines[3]), Some("invalid_request"));
+    assert_eq!(lines[4], detected("Googlebot/2.1")?);
+
+    // the same goes for lines that aren't json at all
+    let response = send(
+        addr,
+        &post("/detect/batch", "\"curl/8.0\"\nnot json\n\n\"Wget/1.21\""),
+    )
+    .await?;
+    let lines = batch_lines(&dechunk(&response)?)?;
+    assert_eq!(lines.len(), 3, "{:?}", lines);
+    assert_eq!(lines[0], detected("curl/8.0")?);
+    assert_eq!(error_kind(&lines[1]), Some("invalid_request"));
+    assert_eq!(lines[2], detected("Wget/1.21")?);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_http_batch_streams() -> Result<()> {
+    let addr = start(Options::default()).await?;
+
+    let mut stream = TcpStream::connect(addr).await?;
+    stream
+        .write_all(b"POST /detect/batch HTTP/1.1\r\nHost: test\r\nConnection: close\r\nTransfer-Encoding: chunked\r\n\r\n")
+        .await?;
+
+    // a full chunk's worth of lines, with the request still going
+    let lines = "\"curl/8.0\"\n".repeat(1000);
+    stream
+        .write_all(format!("{:x}\r\n{}\r\n", lines.len(), lines).as_bytes())
+        .await?;
+
+    // results come back before the rest of the request is sent
+    let mut response = Vec::new();
+    let mut buffer = [0; 64 * 1024];
+    while String::from_utf8_lossy(&response)
+        .matches("\"name\":\"curl\"")
+        .count()
+        < 1000
+    {
+        let read =
+            tokio::time::timeout(Duration::from_secs(30), stream.read(&mut buffer)).await??;
+        anyhow::ensure!(
+            read > 0,
+            "closed early: {}",
+            String::from_utf8_lossy(&response)
+        );
+        response.extend_from_slice(&buffer[..read]);
+    }
+
+    let last = "\"Wget/1.21\"";
+    stream
+        .write_all(format!("{:x}\r\n{}\r\n0\r\n\r\n", last.len(), last).as_bytes())
+        .await?;
+    stream.read_to_end(&mut response).await?;
+
+    let lines = batch_lines(&dechunk(&String::from_utf8(response)?)?)?;
+    assert_eq!(lines.len(), 1001);
+    assert_eq!(lines[0], detected("curl/8.0")?);
+    assert_eq!(lines[1000], detected("Wget/1.21")?);
+
+    Ok(())
+}
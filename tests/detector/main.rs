@@ -0,0 +1,15 @@
+mod batch;
+mod borrowed;
+mod cache;
+#[cfg(feature = "build-binary")]
+mod cli;
+mod compile;
+mod diagnostics;
+mod errors;
+mod headers;
+#[cfg(feature = "build-binary")]
+mod http;
+mod options;
+mod regex_dir;
+mod short_names;
+mod utils;
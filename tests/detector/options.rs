@@ -0,0 +1,154 @@
+use anyhow::Result;
+
+use std::sync::Arc;
+
+use rust_device_detector::cache::ShardedCache;
+use rust_device_detector::device_detector::{Detection, DeviceDetector};
+use rust_device_detector::options::{DetectionOptions, VersionTruncation};
+
+use crate::utils;
+
+#[test]
+fn test_version_truncation() -> Result<()> {
+    let ua = "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/120.0.6099.109 Safari/537.36";
+    let versions = |dd: &DeviceDetector| -> Result<_> {
+        let detection = dd.parse(ua, None)?;
+        let known = detection.get_known_device().unwrap();
+        let client = known.client.clone().unwrap();
+        let os = known.os.clone().unwrap();
+        Ok((client.version, client.engine_version, os.version))
+    };
+
+    let full = versions(&utils::DD)?;
+    assert_eq!(full.0.as_deref(), Some("120.0.6099.109"));
+
+    let truncated = |version_truncation| {
+        utils::DD.with_options(DetectionOptions {
+            version_truncation,
+            ..Default::default()
+        })
+    };
+
+    let (client, engine, os) = versions(&truncated(VersionTruncation::Major))?;
+    assert_eq!(client.as_deref(), Some("120"));
+    assert_eq!(engine.as_deref(), Some("120"));
+    assert_eq!(os.as_deref(), Some("10"));
+
+    let (client, _, _) = versions(&truncated(VersionTruncation::Minor))?;
+    assert_eq!(client.as_deref(), Some("120.0"));
+
+    let (client, _, _) = versions(&truncated(VersionTruncation::Patch))?;
+    assert_eq!(client.as_deref(), Some("120.0.6099"));
+
+    assert_eq!(versions(&truncated(VersionTruncation::Build))?, full);
+    assert_eq!(versions(&truncated(VersionTruncation::None))?, full);
+
+    // the cache keeps whole versions, whatever the options of the detector
+    // that filled it
+    let cache = Arc::new(ShardedCache::new(10, 1));
+    let dd = DeviceDetector::builder()
+        .detection_cache(cache.clone())
+        .options(DetectionOptions {
+            version_truncation: VersionTruncation::Major,
+            ..Default::default()
+        })
+        .build()?;
+    assert_eq!(versions(&dd)?.0.as_deref(), Some("120"));
+    let dd = DeviceDetector::builder().detection_cache(cache).build()?;
+    assert_eq!(versions(&dd)?, full);
+    assert_eq!(dd.cache_stats().hits, 1);
+
+    assert_eq!(
+        "MAJOR".parse::<VersionTruncation>(),
+        Ok(VersionTruncation::Major)
+    );
+    assert!("3".parse::<VersionTruncation>().is_err());
+
+    Ok(())
+}
+
+#[test]
+fn test_bot_options() -> Result<()> {
+    let ua = "Mozilla/5.0 (compatible; Googlebot/2.1; +http://www.google.com/bot.html)";
+
+    let detection = utils::DD.parse(ua, None)?;
+    assert_eq!(detection.get_bot().unwrap().name, "Googlebot");
+
+    let skip = DetectionOptions {
+        skip_bot_detection: true,
+        ..Default::default()
+    };
+    let detection = utils::DD.with_options(skip.clone()).parse(ua, None)?;
+    assert!(!detection.is_bot());
+    assert!(detection.get_known_device().is_some());
+
+    let discard = DetectionOptions {
+        discard_bot_information: true,
+        ..Default::default()
+    };
+    let detection = utils::DD.with_options(discard).parse(ua, None)?;
+    assert!(matches!(detection, Detection::AnonymousBot));
+    assert!(detection.is_bot());
+    assert_eq!(detection.to_value(), serde_json::json!({ "bot": true }));
+
+    // whether bots were looked for is part of the cache key, so detectors
+    // with and without it can share a cache
+    let cache = Arc::new(ShardedCache::new(10, 1));
+    let dd = DeviceDetector::builder()
+        .detection_cache(cache.clone())
+        .build()?;
+    assert!(dd.parse(ua, None)?.is_bot());
+    let dd = DeviceDetector::builder()
+        .detection_cache(cache)
+        .options(skip)
+        .build()?;
+    assert!(!dd.parse(ua, None)?.is_bot());
+    assert_eq!(dd.cache_stats().entries, 2);
+
+    Ok(())
+}
+
+#[test]
+fn test_detect_bot_devices() -> Result<()> {
+    let ua = "Mozilla/5.0 (Linux; Android 6.0.1; Nexus 5X Build/MMB29P) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/120.0.6099.109 Mobile Safari/537.36 (compatible; Googlebot/2.1; +http://www.google.com/bot.html)";
+
+    assert!(matches!(utils::DD.parse(ua, None)?, Detection::Bot(_)));
+
+    let options = DetectionOptions {
+        detect_bot_devices: true,
+        ..Default::default()
+    };
+    let detection = utils::DD.with_options(options.clone()).parse(ua, None)?;
+    assert!(detection.is_bot());
+    assert_eq!(detection.get_bot().unwrap().name, "Googlebot");
+
+    let known = detection.get_known_device().unwrap();
+    assert!(known.is_smart_phone());
+    assert_eq!(known.os.as_ref().unwrap().name, "Android");
+    assert_eq!(known.client.as_ref().unwrap().name, "Chrome Mobile");
+
+    let value = detection.to_value();
+    assert_eq!(value["bot"]["name"], "Googlebot");
+    assert_eq!(value["device"]["type"], "smartphone");
+    assert_eq!(value["is"]["robot"], true);
+
+    // anyone else is detected as before, without a bot in the json
+    let chrome = "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/120.0.0.0 Safari/537.36";
+    let value = utils::DD
+        .with_options(options)
+        .parse(chrome, None)?
+        .to_value();
+    assert!(value.get("bot").is_none());
+    assert_eq!(value["is"]["robot"], false);
+
+    // discarding bot information still wins
+    let options = DetectionOptions {
+        detect_bot_devices: true,
+        discard_bot_information: true,
+        ..Default::default()
+    };
+    let detection = utils::DD.with_options(options).parse(ua, None)?;
+    assert!(matches!(detection, Detection::AnonymousBot));
+
+    Ok(())
+}
@@ -0,0 +1,99 @@
+use anyhow::Result;
+use serde_yaml::Value;
+
+use std::path::Path;
+
+use rust_device_detector::device_detector::DeviceDetector;
+
+use crate::utils;
+
+#[test]
+fn test_regex_dir_matches_embedded() -> Result<()> {
+    let dd = DeviceDetector::from_regex_dir("regexes")?;
+
+    let fixtures = utils::yaml_files("tests/data/fixtures")?;
+    let fixtures: Vec<_> = fixtures
+        .iter()
+        .filter(|path| path.parent() == Some(Path::new("tests/data/fixtures")))
+        .collect();
+    assert!(fixtures.len() > 70, "{:?}", fixtures);
+
+    for path in fixtures {
+        let cases: Value = serde_yaml::from_str(&std::fs::read_to_string(path)?)?;
+
+        for case in cases.as_sequence().expect("sequence") {
+            let ua = case["user_agent"].as_str().expect("user_agent");
+
+            let embedded = utils::DD.parse(ua, None)?.to_value();
+            let from_dir = dd.parse(ua, None)?.to_value();
+
+            assert_eq!(embedded, from_dir, "{}, ua: {}", path.display(), ua);
+        }
+    }
+
+    Ok(())
+}
+
+// The embedded regexes are converted to messagepack by build.rs, from the
+// same yaml that from_regex_dir reads, and both have to be parsed into the
+// same lists. They are compared file by file, so that if they aren't, the
+// failure says where.
+#[test]
+fn test_embedded_matches_yaml() -> Result<()> {
+    let from_dir = DeviceDetector::from_regex_dir("regexes")?.regex_files();
+    let embedded = utils::DD.regex_files();
+
+    // every file in the directory is one the database is loaded from
+    let mut paths: Vec<_> = embedded
+        .iter()
+        .map(|(path, _)| Path::new("regexes").join(path))
+        .collect();
+    paths.sort();
+    assert_eq!(paths, utils::yaml_files("regexes")?);
+
+    for ((path, yaml), (_, msgpack)) in from_dir.iter().zip(&embedded) {
+        let differs = yaml
+            .lines()
+            .zip(msgpack.lines())
+            .position(|(yaml, msgpack)| yaml != msgpack);
+
+        if let Some(line) = differs {
+            panic!(
+                "{} line {} of its parsed lists differs:\n  yaml:     {}\n  embedded: {}",
+                path,
+                line + 1,
+                yaml.lines().nth(line).unwrap_or_default(),
+                msgpack.lines().nth(line).unwrap_or_default()
+            );
+        }
+        assert_eq!(yaml.len(), msgpack.len(), "{}", path);
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_regex_dir_is_used() -> Result<()> {
+    let dir = utils::regexes_with_bot("regex-dir", "RegexDirTestAgent", "Regex Dir Test Agent")?;
+    let dd = DeviceDetector::builder().regex_dir(&*dir).build()?;
+
+    let ua = "RegexDirTestAgent/1.0";
+
+    let res = dd.parse(ua, None)?;
+    assert_eq!(
+        res.get_bot().map(|bot| bot.name.as_str()),
+        Some("Regex Dir Test Agent")
+    );
+
+    assert!(!utils::DD.parse(ua, None)?.is_bot());
+
+    Ok(())
+}
+
+#[test]
+fn test_regex_dir_missing() {
+    let err = DeviceDetector::from_regex_dir("does/not/exist")
+        .expect_err("missing regex dir should fail");
+
+    assert!(format!("{:#}", err).contains("bots.yml"), "{:#}", err);
+}
@@ -0,0 +1,74 @@
+use anyhow::Result;
+
+use rust_device_detector::known_brands::AvailableBrands;
+use rust_device_detector::known_browsers::AvailableBrowsers;
+use rust_device_detector::known_oss::AvailableOSs;
+
+use crate::utils;
+
+#[test]
+fn test_short_names() -> Result<()> {
+    let ua = "Mozilla/5.0 (Linux; Android 10; K) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/120.0.0.0 Mobile Safari/537.36";
+    let detection = utils::DD.parse(ua, None)?;
+    let known = detection.get_known_device().expect("known device");
+    let client = known.client.as_ref().expect("client");
+    let os = known.os.as_ref().expect("os");
+
+    assert_eq!(client.short_name.as_deref(), Some("CM"));
+    assert_eq!(os.short_name.as_deref(), Some("AND"));
+
+    let value = detection.clone().to_value();
+    assert_eq!(value["client"]["short_name"], "CM");
+    assert_eq!(value["os"]["short_name"], "AND");
+
+    let browsers = AvailableBrowsers::default();
+    assert_eq!(browsers.search_by_short("CM").unwrap().name, client.name);
+    assert!(browsers.search_by_short("nope").is_none());
+    assert_eq!(
+        AvailableOSs::default().search_by_short("AND").unwrap().name,
+        "Android"
+    );
+
+    // only browsers have short names
+    let library = utils::DD.parse("curl/8.0", None)?;
+    let library = library.get_known_device().unwrap().client.as_ref().unwrap();
+    assert_eq!(library.short_name, None);
+
+    Ok(())
+}
+
+#[test]
+fn test_device_short_name() -> Result<()> {
+    let ua = "Mozilla/5.0 (Linux; Android 13; SM-S908B) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/112.0.0.0 Mobile Safari/537.36";
+    let detection = utils::DD.parse(ua, None)?;
+    let device = detection
+        .get_known_device()
+        .and_then(|known| known.device.as_ref())
+        .expect("device");
+    assert_eq!(device.brand.as_deref(), Some("Samsung"));
+
+    // whatever code the catalog has for the brand, which is none at all
+    // until upstream's table has been imported
+    let short_name = AvailableBrands::default()
+        .search_by_name("Samsung")
+        .expect("known brand")
+        .short_name
+        .clone();
+    assert_eq!(device.short_name, short_name);
+
+    let value = detection.to_value();
+    let device = value["device"].as_object().expect("device");
+    assert_eq!(
+        device.get("short_name"),
+        Some(&serde_json::to_value(&short_name)?)
+    );
+
+    // and the same when borrowed
+    let borrowed = utils::DD.parse_ref(ua, None)?.to_owned();
+    assert_eq!(
+        borrowed.to_value()["device"]["short_name"],
+        value["device"]["short_name"]
+    );
+
+    Ok(())
+}
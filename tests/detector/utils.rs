@@ -0,0 +1,103 @@
+use anyhow::Result;
+
+use once_cell::sync::Lazy;
+
+use std::ops::Deref;
+use std::path::{Path, PathBuf};
+
+use rust_device_detector::device_detector::DeviceDetector;
+
+pub(crate) static DD: Lazy<DeviceDetector> = Lazy::new(DeviceDetector::new);
+
+/// A reduced user agent, which leaves the model to the client hints.
+#[cfg(feature = "build-binary")]
+pub(crate) const REDUCED_ANDROID: &str = "Mozilla/5.0 (Linux; Android 10; K) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/120.0.0.0 Mobile Safari/537.36";
+
+/// The client hints sent along with [`REDUCED_ANDROID`]. Browsers put a colon
+/// into one of the brands on purpose, to catch out parsers that split on
+/// every colon.
+#[cfg(feature = "build-binary")]
+pub(crate) fn android_client_hints() -> Vec<(String, String)> {
+    [
+        (
+            "Sec-CH-UA-Full-Version-List",
+            r#""Not:A-Brand";v="8.0.0.0", "Chromium";v="120.0.6099.71", "Google Chrome";v="120.0.6099.71""#,
+        ),
+        ("Sec-CH-UA-Model", r#""SM-S911B""#),
+        ("Sec-CH-UA-Platform", r#""Android""#),
+    ]
+    .into_iter()
+    .map(|(name, value)| (name.to_owned(), value.to_owned()))
+    .collect()
+}
+
+/// A directory in the system's temp dir, which is deleted again when this is
+/// dropped.
+pub(crate) struct ScratchDir(PathBuf);
+
+impl ScratchDir {
+    /// Empty, and unique to `name` and this process.
+    pub(crate) fn new(name: &str) -> Self {
+        let path = std::env::temp_dir().join(format!("rdd-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&path);
+        Self(path)
+    }
+}
+
+impl Deref for ScratchDir {
+    type Target = Path;
+
+    fn deref(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl AsRef<Path> for ScratchDir {
+    fn as_ref(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl Drop for ScratchDir {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
+
+fn copy_dir(from: &Path, to: &Path) -> Result<()> {
+    std::fs::create_dir_all(to)?;
+    for entry in std::fs::read_dir(from)? {
+        let entry = entry?;
+        let target = to.join(entry.file_name());
+        if entry.file_type()?.is_dir() {
+            copy_dir(&entry.path(), &target)?;
+        } else {
+            std::fs::copy(entry.path(), target)?;
+        }
+    }
+    Ok(())
+}
+
+/// A copy of `regexes/` with a bot of our own at the top of bots.yml, so it is
+/// tried before any of the real ones.
+pub(crate) fn regexes_with_bot(name: &str, regex: &str, bot_name: &str) -> Result<ScratchDir> {
+    let dir = ScratchDir::new(name);
+    copy_dir(Path::new("regexes"), &dir)?;
+    prepend_bot(&dir, regex, bot_name)?;
+    Ok(dir)
+}
+
+/// Puts another bot at the top of the bots.yml in `dir`.
+pub(crate) fn prepend_bot(dir: &Path, regex: &str, bot_name: &str) -> Result<()> {
+    let bots = std::fs::read_to_string(dir.join("bots.yml"))?;
+    let bots = format!("- regex: '{}'\n  name: '{}'\n\n{}", regex, bot_name, bots);
+    std::fs::write(dir.join("bots.yml"), bots)?;
+    Ok(())
+}
+
+/// Every yaml file under `dir`, in a stable order.
+pub(crate) fn yaml_files(dir: &str) -> Result<Vec<PathBuf>> {
+    let mut files = glob::glob(&format!("{}/**/*.yml", dir))?.collect::<Result<Vec<_>, _>>()?;
+    files.sort();
+    Ok(files)
+}
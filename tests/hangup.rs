@@ -0,0 +1,79 @@
+// Sends SIGHUP to the whole process, which every server in it with reloading
+// enabled would take as a reload, so this is a test binary of its own with a
+// single test in it.
+#![cfg(all(unix, feature = "build-binary"))]
+
+use anyhow::Result;
+
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+
+use rust_device_detector::cache::{DetectionCache, ShardedCache};
+use rust_device_detector::device_detector::DeviceDetector;
+use rust_device_detector::http::{server_with_options, Options, Reload};
+
+async fn send(addr: SocketAddr, request: &str) -> Result<String> {
+    let mut stream = TcpStream::connect(addr).await?;
+    stream.write_all(request.as_bytes()).await?;
+    let mut response = String::new();
+    stream.read_to_string(&mut response).await?;
+    Ok(response)
+}
+
+#[tokio::test]
+async fn test_reload_on_hangup() -> Result<()> {
+    let cache = Arc::new(ShardedCache::new(100, 4));
+    let builder = DeviceDetector::builder().detection_cache(cache.clone());
+
+    let addr = std::net::TcpListener::bind("127.0.0.1:0")?.local_addr()?;
+    let options = Options {
+        reload: Some(Reload {
+            builder: builder.clone(),
+            watch_interval: None,
+            smoke_corpus: Vec::new(),
+            warm_up_threads: None,
+            token: None,
+        }),
+        ..Options::default()
+    };
+    tokio::spawn(server_with_options(addr, builder.build()?, options));
+
+    // the signal handler is in place by the time the server is listening
+    let detect = "POST /detect HTTP/1.1\r\nHost: test\r\nConnection: close\r\nContent-Length: 8\r\n\r\ncurl/8.0";
+    let mut response = String::new();
+    for _ in 0..100 {
+        if let Ok(detected) = send(addr, detect).await {
+            response = detected;
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+    assert!(response.contains("\"name\":\"curl\""), "{}", response);
+    assert_eq!(cache.entries(), 1);
+
+    let status = std::process::Command::new("kill")
+        .arg("-HUP")
+        .arg(std::process::id().to_string())
+        .status()?;
+    assert!(status.success());
+
+    // the old detector's cache is cleared once the new one is swapped in
+    for _ in 0..500 {
+        if cache.entries() == 0 {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+    assert_eq!(cache.entries(), 0, "the server never reloaded on SIGHUP");
+
+    // and the new one caches elsewhere
+    let response = send(addr, detect).await?;
+    assert!(response.contains("\"name\":\"curl\""), "{}", response);
+    assert_eq!(cache.entries(), 0);
+
+    Ok(())
+}
@@ -42,7 +42,7 @@ fn base_fixture_tests(file_path: &str, contents: &str) {
 
     let cases = cases.as_sequence_mut().expect("sequence");
 
-    for (i, case) in cases.into_iter().enumerate() {
+    for (i, case) in cases.iter_mut().enumerate() {
         basic(file_path, i + 1, case).expect("basic test");
     }
 }
@@ -1,3 +1,4 @@
+mod brands;
 mod camera;
 mod car_browser;
 mod console;
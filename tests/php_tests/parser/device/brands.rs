@@ -0,0 +1,89 @@
+use anyhow::Result;
+use serde_yaml::Value;
+
+use rust_device_detector::known_brands::AvailableBrands;
+
+use crate::utils;
+
+/// Every brand named in the device regexes.
+fn referenced_brands() -> Result<Vec<String>> {
+    let mut referenced = Vec::new();
+
+    for file in utils::files("regexes/device/*.yml")? {
+        let devices: Value = serde_yaml::from_reader(file)?;
+
+        for (brand, device) in devices.as_mapping().expect("mapping") {
+            referenced.push(brand.as_str().expect("brand").to_owned());
+
+            let models = device.get("models").and_then(|x| x.as_sequence());
+            for model in models.into_iter().flatten() {
+                if let Some(brand) = model.get("brand").and_then(|x| x.as_str()) {
+                    referenced.push(brand.to_owned());
+                }
+            }
+        }
+    }
+
+    let fragments: Value =
+        serde_yaml::from_reader(std::fs::File::open("regexes/vendorfragments.yml")?)?;
+    for brand in fragments.as_mapping().expect("mapping").keys() {
+        referenced.push(brand.as_str().expect("brand").to_owned());
+    }
+
+    assert!(referenced.len() > 1000, "expected the device regexes");
+    referenced.retain(|brand| brand != "Unknown");
+
+    Ok(referenced)
+}
+
+#[test]
+fn test_known_brands() -> Result<()> {
+    let brands = AvailableBrands::default();
+
+    for brand in referenced_brands()? {
+        let known = brands.search_by_name(&brand);
+        assert!(
+            known.is_some_and(|known| known.name == brand),
+            "brand {} is missing from known_brands.rs",
+            brand
+        );
+    }
+
+    assert_eq!(
+        brands
+            .search_by_name("sony ericsson")
+            .map(|x| x.name.as_str()),
+        Some("Sony Ericsson")
+    );
+    assert!(brands.search_by_name("Unknown").is_none());
+    assert_eq!(brands.iter().count(), brands.len());
+
+    Ok(())
+}
+
+// upstream's own tests hold its table to the same: every brand in the regexes
+// has a code, and no two brands share one.
+#[test]
+#[ignore = "upstream's $deviceBrands hasn't been imported yet, see copy_static_files.sh"]
+fn test_known_brand_codes() -> Result<()> {
+    let brands = AvailableBrands::default();
+
+    for brand in referenced_brands()? {
+        let short = brands
+            .search_by_name(&brand)
+            .and_then(|known| known.short_name.as_deref());
+        let Some(short) = short else {
+            panic!("brand {} has no short code", brand);
+        };
+        assert_eq!(
+            brands.search_by_short(short).map(|x| x.name.as_str()),
+            Some(brand.as_str()),
+            "short code {}",
+            short
+        );
+    }
+
+    assert!(brands.search_by_short("XX").is_none());
+
+    Ok(())
+}
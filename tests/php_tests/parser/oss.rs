@@ -14,7 +14,7 @@ fn test_oss() -> Result<()> {
         let mut cases: Value = serde_yaml::from_reader(file)?;
         let cases = cases.as_sequence_mut().expect("sequence");
 
-        for (i, case) in cases.into_iter().enumerate() {
+        for (i, case) in cases.iter_mut().enumerate() {
             basic(i + 1, case).expect("basic test");
         }
     }
@@ -50,6 +50,18 @@ fn basic(idx: usize, value: &mut Value) -> Result<()> {
         ua
     );
 
+    let test_os_short_name: Option<&str> = test_os["short_name"].as_str();
+    let dd_os_short_name: Option<&str> = dd_os.and_then(|os| os.short_name.as_deref());
+
+    assert!(
+        test_os_short_name == dd_os_short_name,
+        "os short name test case: {}\n code: {:?}\n test: {:?} ua: {}",
+        idx,
+        dd_os,
+        test_os,
+        ua
+    );
+
     let dd_os_version: Option<&str> = dd_os.and_then(|os| os.version.as_deref());
     let test_os_version: &Value = &test_os["version"];
     let test_os_version: Option<String> = test_os_version